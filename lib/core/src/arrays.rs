@@ -0,0 +1,147 @@
+use crate::{as_static, singleton, slice_key, BitwiseFloat, Cacher, InternHandle, UNDEFINED};
+use js_sys::{DataView, Float32Array, Float64Array, Uint8Array};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+thread_local!(
+	static POINT_CACHE: Cacher<(BitwiseFloat, BitwiseFloat)> = Cacher::new();
+	static BASE64_CACHE: Cacher<&'static str> = Cacher::new();
+	// Owned bytes are kept as the key, so that they are freed with the cache.
+	static BYTES_CACHE: Cacher<Cow<'static, [u8]>> = Cacher::new();
+	static F64_ARRAY_CACHE: Cacher<Box<[BitwiseFloat]>> = Cacher::new();
+	// Reused to build the key for each lookup, so that only misses allocate.
+	static F64_ARRAY_SCRATCH: RefCell<Vec<BitwiseFloat>> = RefCell::default();
+	static F32_ARRAY_CACHE: Cacher<Box<[u32]>> = Cacher::new();
+	static F32_ARRAY_SCRATCH: RefCell<Vec<u32>> = RefCell::default();
+	static UTF8_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	static DATAVIEW_CACHE: Cacher<&'static [u8]> = Cacher::new();
+	static DELTAS_CACHE: Cacher<(usize, usize)> = Cacher::new();
+);
+
+pub(crate) fn clear_caches() {
+	POINT_CACHE.with(|c| c.clear());
+	BASE64_CACHE.with(|c| c.clear());
+	BYTES_CACHE.with(|c| c.clear());
+	F64_ARRAY_CACHE.with(|c| c.clear());
+	F32_ARRAY_CACHE.with(|c| c.clear());
+	UTF8_CACHE.with(|c| c.clear());
+	DATAVIEW_CACHE.with(|c| c.clear());
+	DELTAS_CACHE.with(|c| c.clear());
+}
+
+/// Stores one copy of each distinct 2D point as a ```Float64Array``` of length 2.
+/// Any time ```intern_point(x, y)``` is called with the same coordinates, the same
+/// instance of the JavaScript array is used. Coordinates are compared by their bit
+/// patterns, the same as ```js_intern!``` does for floats.
+pub fn intern_point(x: f64, y: f64) -> InternHandle {
+	InternHandle::from_raw(POINT_CACHE.with(|c| {
+		c.cache_with((x.into(), y.into()), |_| Float64Array::from(&[x, y][..]).into())
+	}))
+}
+
+/// Stores one copy of each distinct byte slice as a ```Uint8Array```.
+/// Slices are compared by their contents, so equal slices at different addresses share one array.
+pub fn intern_bytes(bytes: &'static [u8]) -> InternHandle {
+	intern_cow_bytes(Cow::Borrowed(bytes))
+}
+
+/// Interns bytes which may or may not be ```'static```, sharing the same cache as ```intern_bytes```.
+/// Owned bytes are kept by the cache the first time their contents are seen, and dropped otherwise.
+pub fn intern_cow_bytes(bytes: Cow<'static, [u8]>) -> InternHandle {
+	InternHandle::from_raw(BYTES_CACHE.with(|c| {
+		match c.get(&bytes[..]) {
+			Some(value) => value,
+			None => c.cache_with(bytes, |bytes| Uint8Array::from(&bytes[..]).into()),
+		}
+	}))
+}
+
+/// Interns optional bytes, such as an optional binary field. ```Some``` is the same ```Uint8Array``` as ```intern_bytes```,
+/// and ```None``` is ```undefined```, which is how wasm-bindgen converts ```None``` and the same as ```js_intern!(None::<char>)```.
+pub fn intern_opt_bytes(bytes: Option<&'static [u8]>) -> InternHandle {
+	match bytes {
+		Some(bytes) => intern_bytes(bytes),
+		None => InternHandle::from_raw(singleton(&UNDEFINED)),
+	}
+}
+
+/// Stores one copy of each distinct sequence of floats as a ```Float64Array```. Unlike the other slice helpers
+/// this takes any slice, such as one borrowed from a ```Vec``` built at runtime, and compares it by contents.
+/// Each element is compared by its bit pattern, the same as ```js_intern!``` does for floats.
+///
+/// The key for each lookup is built in a reused buffer, so that a value which was already interned
+/// can be found without allocating. Only the first time a sequence is seen is it copied.
+pub fn intern_f64_vec_to_typed(values: &[f64]) -> InternHandle {
+	F64_ARRAY_SCRATCH.with(|scratch| {
+		let mut key = scratch.borrow_mut();
+		key.clear();
+		key.extend(values.iter().map(|&value| BitwiseFloat::from(value)));
+		InternHandle::from_raw(F64_ARRAY_CACHE.with(|c| {
+			match c.get(&key[..]) {
+				Some(value) => value,
+				None => c.cache_with(key[..].into(), |_| Float64Array::from(values).into()),
+			}
+		}))
+	})
+}
+
+/// Stores one copy of each distinct sequence of floats as a ```Float32Array```, such as vertex data for WebGL.
+/// Slices are compared by the bit patterns of their contents, so equal slices at different addresses share one array.
+/// As with ```intern_f64_vec_to_typed```, looking up a sequence which was already interned doesn't allocate.
+pub fn intern_f32_slice(values: &'static [f32]) -> InternHandle {
+	F32_ARRAY_SCRATCH.with(|scratch| {
+		let mut key = scratch.borrow_mut();
+		key.clear();
+		key.extend(values.iter().map(|value| value.to_bits()));
+		InternHandle::from_raw(F32_ARRAY_CACHE.with(|c| {
+			match c.get(&key[..]) {
+				Some(value) => value,
+				None => c.cache_with(key[..].into(), |_| Float32Array::from(values).into()),
+			}
+		}))
+	})
+}
+
+/// Stores the UTF-8 bytes of a string as a ```Uint8Array```, for JavaScript APIs which take encoded text.
+/// The array is keyed by the address of the string rather than its contents, so each ```'static``` string
+/// is only copied once, without going through a ```TextEncoder```.
+pub fn intern_str_utf8_bytes(s: &'static str) -> InternHandle {
+	InternHandle::from_raw(UTF8_CACHE.with(|c| {
+		c.cache_with(slice_key(s.as_bytes()), |_| Uint8Array::from(s.as_bytes()).into())
+	}))
+}
+
+/// Stores one copy of each distinct byte slice as a ```DataView``` over its own ```ArrayBuffer```, for reading fields
+/// of binary protocol frames such as repeated headers. Slices are compared by their contents, so equal slices at
+/// different addresses share one view.
+///
+/// The view should only be read from. Writing through it, or through another view of its buffer, would be seen by every user of it.
+pub fn intern_dataview(bytes: &'static [u8]) -> InternHandle {
+	InternHandle::from_raw(DATAVIEW_CACHE.with(|c| {
+		c.cache_with(bytes, |bytes| {
+			let buffer = Uint8Array::from(*bytes).buffer();
+			DataView::new(&buffer, 0, bytes.len()).into()
+		})
+	}))
+}
+
+/// Interns a ```Float64Array``` of the differences between consecutive values of ```data```, eg: ```[1.0, 3.0, 2.0]```
+/// becomes ```[2.0, -1.0]```, for compressing time series or drawing them as steps. The array is one shorter than ```data```,
+/// and empty for fewer than two values. It is keyed by the address of the slice, so the deltas of a ```static``` table are only computed once.
+pub fn intern_deltas(data: &'static [f64]) -> InternHandle {
+	InternHandle::from_raw(DELTAS_CACHE.with(|c| {
+		c.cache_with(slice_key(data), |_| {
+			let deltas: Vec<f64> = data.windows(2).map(|pair| pair[1] - pair[0]).collect();
+			Float64Array::from(&deltas[..]).into()
+		})
+	}))
+}
+
+#[doc(hidden)]
+/// This is a private function used by js_intern_base64! and not meant to be used.
+pub fn intern_base64__(source: &'static str, bytes: &'static [u8]) -> &'static JsValue {
+	as_static(BASE64_CACHE.with(|c| {
+		c.cache_with(source, |_| Uint8Array::from(bytes).into())
+	}))
+}
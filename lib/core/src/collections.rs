@@ -0,0 +1,256 @@
+#[cfg(feature = "strings")]
+use crate::slice_key;
+#[cfg(feature = "numbers")]
+use crate::BitwiseFloat;
+use crate::{as_static, CacheJsIntern__, Cacher, InternHandle};
+#[cfg(feature = "strings")]
+use js_sys::{Map, Object, Reflect, Set};
+use js_sys::{Array, WeakMap};
+use std::cell::RefCell;
+#[cfg(feature = "numbers")]
+use std::ops::Range;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+thread_local!(
+	#[cfg(feature = "strings")]
+	static STRING_SET_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	#[cfg(feature = "strings")]
+	static STRING_MAP_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	#[cfg(feature = "bools")]
+	static BOOL_ARRAY_CACHE: Cacher<&'static [bool]> = Cacher::new();
+	#[cfg(feature = "numbers")]
+	static RANGE_CACHE: Cacher<(i32, i32)> = Cacher::new();
+	#[cfg(feature = "numbers")]
+	static F64_RANGE_CACHE: Cacher<(BitwiseFloat, BitwiseFloat)> = Cacher::new();
+	// Keyed by the addresses of the interned strings, which are unique to their contents.
+	#[cfg(feature = "strings")]
+	static KV_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	#[cfg(feature = "strings")]
+	static OBJECT_CACHE: Cacher<Box<[(&'static str, *const JsValue)]>> = Cacher::new();
+	#[cfg(feature = "strings")]
+	static FROZEN_STR_ARRAY_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	#[cfg(feature = "strings")]
+	static STATS_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	// Created the first time it is used, since creating it calls into JavaScript.
+	static ASSOCIATIONS: RefCell<Option<WeakMap>> = RefCell::default();
+);
+
+pub(crate) fn clear_caches() {
+	#[cfg(feature = "strings")]
+	{
+		STRING_SET_CACHE.with(|c| c.clear());
+		STRING_MAP_CACHE.with(|c| c.clear());
+		clear_string_pointers();
+		FROZEN_STR_ARRAY_CACHE.with(|c| c.clear());
+		STATS_CACHE.with(|c| c.clear());
+	}
+	#[cfg(feature = "bools")]
+	BOOL_ARRAY_CACHE.with(|c| c.clear());
+	#[cfg(feature = "numbers")]
+	{
+		RANGE_CACHE.with(|c| c.clear());
+		F64_RANGE_CACHE.with(|c| c.clear());
+	}
+	// The interned objects are freed, so nothing can be associated with them anymore.
+	ASSOCIATIONS.with(|a| a.borrow_mut().take());
+}
+
+#[cfg(feature = "strings")]
+/// Frees the caches keyed by the addresses of interned strings, for when only the string cache is freed,
+/// since new strings could be given the same addresses.
+pub(crate) fn clear_string_pointers() {
+	KV_CACHE.with(|c| c.clear());
+	OBJECT_CACHE.with(|c| c.clear());
+}
+
+#[cfg(feature = "strings")]
+/// Interns a JavaScript ```Set``` of the interned strings in ```items```, for membership checks on the JavaScript side.
+/// The set is keyed by the address of the slice rather than its contents, so a ```static``` or ```const``` table
+/// is built only once. Duplicate items are collapsed by the set.
+pub fn intern_string_set(items: &'static [&'static str]) -> InternHandle {
+	InternHandle::from_raw(STRING_SET_CACHE.with(|c| {
+		c.cache_with(slice_key(items), |_| {
+			let set = Set::new(&JsValue::UNDEFINED);
+			for item in items {
+				set.add(as_static(item.cache_js_intern__()));
+			}
+			set.into()
+		})
+	}))
+}
+
+#[cfg(feature = "strings")]
+/// Interns a JavaScript ```Map``` from the interned keys to the interned values in ```pairs```, for lookups on the JavaScript side.
+/// Like ```intern_string_set```, the map is keyed by the address of the slice. If a key appears more than once, the last value wins.
+pub fn intern_string_map(pairs: &'static [(&'static str, &'static str)]) -> InternHandle {
+	InternHandle::from_raw(STRING_MAP_CACHE.with(|c| {
+		c.cache_with(slice_key(pairs), |_| {
+			let map = Map::new();
+			for (key, value) in pairs {
+				map.set(as_static(key.cache_js_intern__()), as_static(value.cache_js_intern__()));
+			}
+			map.into()
+		})
+	}))
+}
+
+#[cfg(feature = "bools")]
+/// Interns a JavaScript ```Array``` of the interned bools in ```flags```. Unlike the helpers above,
+/// this is keyed by the contents of the slice, so equal slices share one array.
+pub fn intern_bool_jsarray(flags: &'static [bool]) -> InternHandle {
+	InternHandle::from_raw(BOOL_ARRAY_CACHE.with(|c| {
+		c.cache_with(flags, |flags| {
+			flags.iter().map(|flag| as_static(flag.cache_js_intern__())).collect::<Array>().into()
+		})
+	}))
+}
+
+#[cfg(feature = "strings")]
+/// Interns a JavaScript ```Array``` of the interned strings in ```items```, frozen with ```Object.freeze```
+/// so that JavaScript can't modify a table which is shared by every user of it. Like ```intern_string_set```,
+/// the array is keyed by the address of the slice, so a ```static``` or ```const``` table is built and frozen only once.
+pub fn intern_str_array_frozen(items: &'static [&'static str]) -> InternHandle {
+	InternHandle::from_raw(FROZEN_STR_ARRAY_CACHE.with(|c| {
+		c.cache_with(slice_key(items), |_| {
+			let array = items.iter().map(|item| as_static(item.cache_js_intern__())).collect::<Array>();
+			Object::freeze(&array).into()
+		})
+	}))
+}
+
+#[cfg(feature = "numbers")]
+/// Interns a range as a JavaScript ```Array``` of ```[start, end]```, where ```end``` is exclusive as in Rust.
+/// Both elements are the same instances as ```js_intern!``` of the numbers, and equal ranges share one array.
+pub fn intern_range(range: Range<i32>) -> InternHandle {
+	InternHandle::from_raw(RANGE_CACHE.with(|c| {
+		c.cache_with((range.start, range.end), |&(start, end)| {
+			Array::of2(as_static(start.cache_js_intern__()), as_static(end.cache_js_intern__())).into()
+		})
+	}))
+}
+
+#[cfg(feature = "numbers")]
+/// Interns the bounds of a histogram bucket or other range of floats as a JavaScript ```Array``` of ```[lo, hi]```, for labeling
+/// them. Like ```intern_range```, both elements are the same instances as ```js_intern!``` of the numbers, and equal ranges
+/// share one array, where the bounds are compared by their bit patterns, the same as ```js_intern!``` does for floats.
+pub fn intern_f64_range(lo: f64, hi: f64) -> InternHandle {
+	InternHandle::from_raw(F64_RANGE_CACHE.with(|c| {
+		c.cache_with((lo.into(), hi.into()), |_| {
+			Array::of2(as_static(lo.cache_js_intern__()), as_static(hi.cache_js_intern__())).into()
+		})
+	}))
+}
+
+#[cfg(feature = "strings")]
+/// Interns a JavaScript object of ```{ key: k, value: v }```, where both fields are the same instances as
+/// ```js_intern!``` of the strings. Equal pairs share one object.
+pub fn intern_kv(k: &'static str, v: &'static str) -> InternHandle {
+	let k = k.cache_js_intern__();
+	let v = v.cache_js_intern__();
+	InternHandle::from_raw(KV_CACHE.with(|c| {
+		c.cache_with((k as usize, v as usize), |_| {
+			let object = Object::new();
+			// Setting a property of a new plain object can't fail.
+			Reflect::set(&object, as_static("key".cache_js_intern__()), as_static(k)).unwrap_throw();
+			Reflect::set(&object, as_static("value".cache_js_intern__()), as_static(v)).unwrap_throw();
+			object.into()
+		})
+	}))
+}
+
+#[cfg(feature = "strings")]
+/// Interns a JavaScript object of ```{ min, max, mean }``` computed from ```data```, for datasets which are displayed
+/// repeatedly. The object is keyed by the address of the slice, so the stats of a ```static``` table are only computed once.
+/// NaNs are skipped by ```min``` and ```max``` but make the ```mean``` NaN, and all three are NaN for an empty slice.
+pub fn intern_stats_object(data: &'static [f64]) -> InternHandle {
+	InternHandle::from_raw(STATS_CACHE.with(|c| {
+		c.cache_with(slice_key(data), |_| {
+			let (min, max, mean) = if data.is_empty() {
+				(f64::NAN, f64::NAN, f64::NAN)
+			} else {
+				let min = data.iter().cloned().fold(f64::NAN, f64::min);
+				let max = data.iter().cloned().fold(f64::NAN, f64::max);
+				(min, max, data.iter().sum::<f64>() / data.len() as f64)
+			};
+			let object = Object::new();
+			// Setting a property of a new plain object can't fail.
+			Reflect::set(&object, as_static("min".cache_js_intern__()), &JsValue::from_f64(min)).unwrap_throw();
+			Reflect::set(&object, as_static("max".cache_js_intern__()), &JsValue::from_f64(max)).unwrap_throw();
+			Reflect::set(&object, as_static("mean".cache_js_intern__()), &JsValue::from_f64(mean)).unwrap_throw();
+			object.into()
+		})
+	}))
+}
+
+#[cfg(all(feature = "strings", feature = "numbers"))]
+/// Interns a JavaScript object with a field for each of ```pairs```, where the keys and the numbers are the same instances
+/// as ```js_intern!``` of them, eg: ```{ width: 640, height: 480 }```. Objects with the same fields in the same order
+/// are the same instance, including those from ```js_intern_object!```. If a key appears more than once, the last value wins.
+///
+/// The fields are set in the order of ```pairs```, so ```Object.keys``` lists them in that order, where a repeated key
+/// stays where it first appeared. The exception is keys which look like array indices, such as ```"1"```, which JavaScript
+/// always lists first, in ascending order.
+pub fn intern_record(pairs: &'static [(&'static str, f64)]) -> InternHandle {
+	let fields: Vec<(&'static str, *const JsValue)> = pairs.iter().map(|&(key, value)| (key, value.cache_js_intern__() as *const JsValue)).collect();
+	InternHandle::from_raw(cache_object(&fields))
+}
+
+/// Associates ```obj``` with an interned object, such as one from ```intern_kv``` or ```intern_range```, so that
+/// per-value state can be kept on the JavaScript side and looked up with ```get_association```. The association
+/// is held by a ```WeakMap```, so the garbage collector can collect it along with the object once it is no longer
+/// interned, eg: after ```reset_interner```. Associating again replaces the previous value.
+///
+/// A ```WeakMap``` can only hold objects, so this returns ```false``` without associating anything for
+/// primitives such as interned strings and numbers, and ```true``` otherwise.
+pub fn associate(interned: &JsValue, obj: JsValue) -> bool {
+	if !interned.is_object() && !interned.is_function() {
+		return false;
+	}
+	ASSOCIATIONS.with(|a| {
+		a.borrow_mut().get_or_insert_with(WeakMap::new).set(interned.unchecked_ref(), &obj);
+	});
+	true
+}
+
+/// Returns the value associated with an interned object by ```associate```, if there is one.
+pub fn get_association(interned: &JsValue) -> Option<JsValue> {
+	if !interned.is_object() && !interned.is_function() {
+		return None;
+	}
+	ASSOCIATIONS.with(|a| {
+		let associations = a.borrow();
+		let associations = associations.as_ref()?;
+		let key = interned.unchecked_ref();
+		if associations.has(key) {
+			Some(associations.get(key))
+		} else {
+			None
+		}
+	})
+}
+
+#[cfg(feature = "strings")]
+#[doc(hidden)]
+/// This is a private function used by js_intern_object! and not meant to be used.
+pub fn intern_object__(fields: &[(&'static str, *const JsValue)]) -> &'static JsValue {
+	as_static(cache_object(fields))
+}
+
+#[cfg(feature = "strings")]
+fn cache_object(fields: &[(&'static str, *const JsValue)]) -> *mut JsValue {
+	OBJECT_CACHE.with(|c| {
+		match c.get(fields) {
+			Some(value) => value,
+			None => c.cache_with(fields.into(), |fields| {
+				let object = Object::new();
+				// Set in the order given, which is the order JavaScript lists the keys in.
+				for &(name, value) in fields.iter() {
+					// Setting a property of a new plain object can't fail.
+					Reflect::set(&object, as_static(name.cache_js_intern__()), unsafe { &*value }).unwrap_throw();
+				}
+				object.into()
+			}),
+		}
+	})
+}
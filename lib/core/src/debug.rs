@@ -0,0 +1,228 @@
+#[cfg(debug_assertions)]
+use crate::is_singleton;
+use crate::intern_epoch;
+#[cfg(all(feature = "bools", not(feature = "small")))]
+use crate::BOOL_CACHE;
+#[cfg(feature = "numbers")]
+use crate::FLOAT_CACHE;
+#[cfg(feature = "strings")]
+use crate::{STRING_CACHE, STRING_LIMIT};
+#[cfg(debug_assertions)]
+use std::cell::RefCell;
+#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+use std::cell::Cell;
+#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+use std::collections::HashMap;
+#[cfg(debug_assertions)]
+use std::collections::HashSet;
+use std::panic::Location;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// The number of distinct values a use of ```js_intern!``` can intern before it is reported as likely not a literal.
+#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+const NON_LITERAL_THRESHOLD: usize = 32;
+
+/// The epoch and the distinct values interned by a use of js_intern!, or None once it has been reported.
+#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+type SiteValues = Option<(u64, HashSet<usize>)>;
+
+#[cfg(debug_assertions)]
+thread_local!(
+	static HANDED_OUT: RefCell<HashSet<usize>> = RefCell::default();
+);
+
+#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+thread_local!(
+	static CALL_SITES: RefCell<HashMap<Location<'static>, SiteValues>> = RefCell::default();
+	static ON_NON_LITERAL: Cell<Option<fn(&'static Location<'static>)>> = const { Cell::new(None) };
+);
+
+#[cfg(all(debug_assertions, feature = "track-call-sites", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+	#[wasm_bindgen(js_namespace = console, js_name = warn)]
+	fn console_warn(message: &str);
+}
+
+/// There is no console outside of JavaScript, so the warning goes to stderr instead.
+#[cfg(all(debug_assertions, feature = "track-call-sites", not(target_arch = "wasm32")))]
+fn console_warn(message: &str) {
+	eprintln!("{}", message);
+}
+
+#[doc(hidden)]
+/// This is a private constant used by js_intern! and not meant to be used.
+/// Uses of js_intern! evaluate their value every time when this is set, so that each value can be tracked.
+pub const TRACK_CALL_SITES__: bool = cfg!(all(debug_assertions, feature = "track-call-sites"));
+
+/// Records the address of a value boxed by a Cacher, so that it passes intern_debug_assert_interned!.
+#[cfg(debug_assertions)]
+pub(crate) fn record_handed_out(value: *mut JsValue) {
+	HANDED_OUT.with(|h| {
+		h.borrow_mut().insert(value as usize);
+	});
+}
+
+#[cfg(not(debug_assertions))]
+pub(crate) fn record_handed_out(_value: *mut JsValue) {}
+
+/// Removes the address of a value which a Cacher is about to free, since it may be reused by another allocation.
+#[cfg(debug_assertions)]
+pub(crate) fn forget_handed_out(value: *mut JsValue) {
+	// An Interner in a thread local may be dropped after the addresses are, when the thread exits.
+	let _ = HANDED_OUT.try_with(|h| {
+		h.borrow_mut().remove(&(value as usize));
+	});
+}
+
+#[cfg(not(debug_assertions))]
+pub(crate) fn forget_handed_out(_value: *mut JsValue) {}
+
+#[doc(hidden)]
+/// This is a private function used by intern_debug_assert_interned! and not meant to be used.
+#[track_caller]
+pub fn debug_assert_interned__(value: &JsValue) {
+	#[cfg(debug_assertions)]
+	{
+		let address = value as *const JsValue;
+		let interned = is_singleton(address) || HANDED_OUT.with(|h| h.borrow().contains(&(address as usize)));
+		assert!(interned, "{:?} at {:p} is not a value stored by js-intern, at {}", value, address, Location::caller());
+	}
+	#[cfg(not(debug_assertions))]
+	let _ = value;
+}
+
+/// Panics if ```value``` is not a reference to a value stored by this crate, such as one returned by ```js_intern!```.
+/// This catches bugs where values which were not interned flow where interned ones are expected.
+/// Equal values which were not interned do not pass, since the check is by address.
+///
+/// Like ```debug_assert!```, this is only checked in builds with debug assertions enabled.
+#[macro_export]
+macro_rules! intern_debug_assert_interned {
+	($value:expr) => {
+		if cfg!(debug_assertions) {
+			$crate::debug_assert_interned__($value);
+		}
+	};
+}
+
+#[doc(hidden)]
+/// This is a private function used by js_intern! and not meant to be used.
+#[track_caller]
+pub fn note_call_site__(value: *mut JsValue) {
+	#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+	{
+		let location = Location::caller();
+		let epoch = intern_epoch();
+		let reported = CALL_SITES.with(|sites| {
+			let mut sites = sites.borrow_mut();
+			let site = sites.entry(*location).or_insert_with(|| Some((epoch, HashSet::new())));
+			let values = match site {
+				Some((site_epoch, values)) => {
+					// The values from before the interner was cleared are freed, and their addresses may be reused.
+					if *site_epoch != epoch {
+						*site_epoch = epoch;
+						values.clear();
+					}
+					values
+				}
+				None => return false,
+			};
+			values.insert(value as usize);
+			if values.len() < NON_LITERAL_THRESHOLD {
+				return false;
+			}
+			*site = None;
+			true
+		});
+		// This is outside of the borrow, since the function may intern values itself.
+		if reported {
+			match ON_NON_LITERAL.with(|c| c.get()) {
+				Some(callback) => callback(location),
+				None => console_warn(&format!(
+					"js_intern! at {} has interned {} distinct values, so it is likely not used with a literal. \
+					Each distinct value is kept for the rest of the thread.", location, NON_LITERAL_THRESHOLD)),
+			}
+		}
+	}
+	#[cfg(not(all(debug_assertions, feature = "track-call-sites")))]
+	let _ = value;
+}
+
+/// Sets a function to be called instead of warning on the console when a use of ```js_intern!``` interns many distinct
+/// values, which means it is likely used with an expression rather than a literal, or goes back to warning with ```None```.
+/// The function is given the location of the use, and is only called once for each use on each thread.
+///
+/// Uses are only tracked with the ```track-call-sites``` feature in builds with debug assertions enabled, so this does nothing otherwise.
+/// On native targets, the warning is printed to stderr instead.
+pub fn on_non_literal_intern(callback: Option<fn(&'static Location<'static>)>) {
+	#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+	ON_NON_LITERAL.with(|c| c.set(callback));
+	#[cfg(not(all(debug_assertions, feature = "track-call-sites")))]
+	let _ = callback;
+}
+
+/// Returns a JSON object describing the caches behind ```js_intern!``` on this thread, for telemetry or remote debugging.
+/// Each cache has the number of values in it and the number it can hold without reallocating. Up to ```sample```
+/// of the interned strings are included as well, in no particular order, or none for ```0```.
+/// Eg: ```{"epoch":1,"string_limit":null,"caches":{"strings":{"count":2,"capacity":3,"sample":["a","b"]},"numbers":{"count":0,"capacity":0}}}```
+///
+/// The bool cache is only included without the ```small``` feature, which stores bools as two singletons instead,
+/// and each cache is only included with the feature of the same name.
+#[wasm_bindgen]
+pub fn intern_diagnostics_json(sample: usize) -> String {
+	let mut json = String::new();
+	// Writing to a String can't fail.
+	let _ = write!(json, "{{\"epoch\":{},\"string_limit\":", intern_epoch());
+	#[cfg(feature = "strings")]
+	let limit = STRING_LIMIT.with(|l| l.get());
+	#[cfg(not(feature = "strings"))]
+	let limit: Option<usize> = None;
+	match limit {
+		Some(limit) => { let _ = write!(json, "{}", limit); }
+		None => json.push_str("null"),
+	}
+	json.push_str(",\"caches\":{");
+	#[cfg(feature = "strings")]
+	STRING_CACHE.with(|c| {
+		let _ = write!(json, "\"strings\":{{\"count\":{},\"capacity\":{},\"sample\":[", c.len(), c.capacity());
+		for (i, key) in c.sample_keys(sample, |key| key.clone()).iter().enumerate() {
+			if i != 0 {
+				json.push(',');
+			}
+			push_json_str(&mut json, key);
+		}
+		json.push_str("]},");
+	});
+	#[cfg(not(feature = "strings"))]
+	let _ = sample;
+	#[cfg(feature = "numbers")]
+	FLOAT_CACHE.with(|c| {
+		let _ = write!(json, "\"numbers\":{{\"count\":{},\"capacity\":{}}},", c.len(), c.capacity());
+	});
+	#[cfg(all(feature = "bools", not(feature = "small")))]
+	BOOL_CACHE.with(|c| {
+		let _ = write!(json, "\"bools\":{{\"count\":{},\"capacity\":{}}},", c.len(), c.capacity());
+	});
+	// Each cache is followed by a comma, which is replaced by the closing brace after the last one.
+	if json.ends_with(',') {
+		json.pop();
+	}
+	json.push_str("}}");
+	json
+}
+
+#[cfg(feature = "strings")]
+fn push_json_str(json: &mut String, s: &str) {
+	json.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => json.push_str("\\\""),
+			'\\' => json.push_str("\\\\"),
+			c if (c as u32) < 0x20 => { let _ = write!(json, "\\u{:04x}", c as u32); }
+			c => json.push(c),
+		}
+	}
+	json.push('"');
+}
@@ -0,0 +1,138 @@
+use crate::{BitwiseFloat, Cacher, InternHandle};
+use js_sys::Date;
+use wasm_bindgen::prelude::*;
+
+thread_local!(
+	static PRECISION_CACHE: Cacher<(BitwiseFloat, usize)> = Cacher::new();
+	static SCIENTIFIC_CACHE: Cacher<BitwiseFloat> = Cacher::new();
+	static UNIT_CACHE: Cacher<(BitwiseFloat, &'static str)> = Cacher::new();
+	static HEX_CACHE: Cacher<&'static [u8]> = Cacher::new();
+	static MONEY_CACHE: Cacher<(i64, &'static str)> = Cacher::new();
+	static FIXED_CACHE: Cacher<(i64, u32)> = Cacher::new();
+	static PERCENT_CACHE: Cacher<(BitwiseFloat, u32)> = Cacher::new();
+	static ISO_TIMESTAMP_CACHE: Cacher<BitwiseFloat> = Cacher::new();
+);
+
+pub(crate) fn clear_caches() {
+	PRECISION_CACHE.with(|c| c.clear());
+	SCIENTIFIC_CACHE.with(|c| c.clear());
+	UNIT_CACHE.with(|c| c.clear());
+	HEX_CACHE.with(|c| c.clear());
+	MONEY_CACHE.with(|c| c.clear());
+	FIXED_CACHE.with(|c| c.clear());
+	PERCENT_CACHE.with(|c| c.clear());
+	ISO_TIMESTAMP_CACHE.with(|c| c.clear());
+}
+
+/// Interns ```value``` formatted as a string with exactly ```decimals``` digits after the decimal point,
+/// as by ```format!("{:.*}", decimals, value)```. This matches common display needs like currency and percentages.
+/// The string is only formatted the first time each ```(value, decimals)``` pair is seen.
+pub fn intern_num_str_prec(value: f64, decimals: usize) -> InternHandle {
+	InternHandle::from_raw(PRECISION_CACHE.with(|c| {
+		c.cache_with((value.into(), decimals), |_| JsValue::from_str(&format!("{:.*}", decimals, value)))
+	}))
+}
+
+/// Interns ```value``` formatted in scientific notation, as by ```format!("{:e}", value)```, which keeps
+/// tiny and huge values readable. For example, ```1500.0``` becomes ```"1.5e3"```.
+/// The string is only formatted the first time each ```value``` is seen.
+pub fn intern_num_str_sci(value: f64) -> InternHandle {
+	InternHandle::from_raw(SCIENTIFIC_CACHE.with(|c| {
+		c.cache_with(value.into(), |_| JsValue::from_str(&format!("{:e}", value)))
+	}))
+}
+
+/// Interns ```value``` followed by ```unit```, as by ```format!("{}{}", value, unit)```, for CSS values like ```"42px"```
+/// or ```"100%"```. Whole numbers have no decimal point, so ```42.0``` with ```"px"``` becomes ```"42px"```.
+/// The string is only formatted the first time each ```(value, unit)``` pair is seen.
+pub fn intern_value_unit(value: f64, unit: &'static str) -> InternHandle {
+	InternHandle::from_raw(UNIT_CACHE.with(|c| {
+		c.cache_with((value.into(), unit), |_| JsValue::from_str(&format!("{}{}", value, unit)))
+	}))
+}
+
+/// Interns ```bytes``` as a lowercase hex string with two digits per byte, for displaying checksums and hashes.
+/// Slices are compared by their contents, and the string is only formatted the first time each is seen.
+pub fn intern_bytes_hex(bytes: &'static [u8]) -> InternHandle {
+	InternHandle::from_raw(HEX_CACHE.with(|c| {
+		c.cache_with(bytes, |bytes| {
+			let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+			JsValue::from_str(&hex)
+		})
+	}))
+}
+
+/// Interns an amount of money given in cents, formatted after ```symbol``` with commas between thousands and two
+/// decimal places, so ```123456``` with ```"$"``` becomes ```"$1,234.56"```. Negative amounts put the minus sign first,
+/// as in ```"-$0.05"```. This doesn't depend on ```Intl```, so it is the same in every locale.
+/// The string is only formatted the first time each ```(cents, symbol)``` pair is seen.
+pub fn intern_money_str(cents: i64, symbol: &'static str) -> InternHandle {
+	InternHandle::from_raw(MONEY_CACHE.with(|c| {
+		c.cache_with((cents, symbol), |_| {
+			let magnitude = cents.unsigned_abs();
+			let digits = (magnitude / 100).to_string();
+			let mut money = String::with_capacity(symbol.len() + digits.len() * 4 / 3 + 4);
+			if cents < 0 {
+				money.push('-');
+			}
+			money.push_str(symbol);
+			for (i, digit) in digits.chars().enumerate() {
+				if i != 0 && (digits.len() - i).is_multiple_of(3) {
+					money.push(',');
+				}
+				money.push(digit);
+			}
+			money.push_str(&format!(".{:02}", magnitude % 100));
+			JsValue::from_str(&money)
+		})
+	}))
+}
+
+/// Interns the exact decimal string of a fixed-point number, which is ```value``` divided by ```10^scale```, so
+/// ```12345``` with a scale of ```2``` becomes ```"123.45"```. Unlike ```intern_num_str_prec```, this never goes
+/// through ```f64```, so there is no rounding. There are always ```scale``` digits after the decimal point, and none
+/// for a scale of ```0```. The string is only formatted the first time each ```(value, scale)``` pair is seen.
+pub fn intern_fixed(value: i64, scale: u32) -> InternHandle {
+	InternHandle::from_raw(FIXED_CACHE.with(|c| {
+		c.cache_with((value, scale), |_| {
+			let scale = scale as usize;
+			let mut digits = value.unsigned_abs().to_string();
+			if digits.len() <= scale {
+				digits.insert_str(0, &"0".repeat(scale + 1 - digits.len()));
+			}
+			let (whole, fraction) = digits.split_at(digits.len() - scale);
+			let sign = if value < 0 { "-" } else { "" };
+			let fixed = if scale == 0 { format!("{}{}", sign, whole) } else { format!("{}{}.{}", sign, whole, fraction) };
+			JsValue::from_str(&fixed)
+		})
+	}))
+}
+
+/// Interns a ratio as a percentage with exactly ```decimals``` digits after the decimal point, as by
+/// ```format!("{:.*}%", decimals, ratio * 100.0)```, for progress bars and stats. So ```0.125``` with ```1``` becomes ```"12.5%"```.
+/// The string is only formatted the first time each ```(ratio, decimals)``` pair is seen.
+pub fn intern_percent_str(ratio: f64, decimals: u32) -> InternHandle {
+	InternHandle::from_raw(PERCENT_CACHE.with(|c| {
+		c.cache_with((ratio.into(), decimals), |_| JsValue::from_str(&format!("{:.*}%", decimals as usize, ratio * 100.0)))
+	}))
+}
+
+/// The largest number of milliseconds from the epoch which a JavaScript ```Date``` can hold, in either direction.
+const MAX_DATE_MS: f64 = 8.64e15;
+
+/// Interns ```epoch_ms```, a timestamp in milliseconds since the Unix epoch, formatted as an ISO 8601 string in UTC by
+/// JavaScript's ```Date.prototype.toISOString```, eg: ```0.0``` becomes ```"1970-01-01T00:00:00.000Z"```, for logs and display.
+/// Fractions of a millisecond are dropped, as by a ```Date```. The string is only formatted the first time each millisecond is seen.
+///
+/// Returns ```None``` for timestamps which a ```Date``` can't hold, which are NaN, the infinities and those more than
+/// 100,000,000 days from the epoch, since ```toISOString``` would throw for them.
+pub fn intern_iso_timestamp(epoch_ms: f64) -> Option<InternHandle> {
+	if epoch_ms.is_nan() || epoch_ms.abs() > MAX_DATE_MS {
+		return None;
+	}
+	// A Date truncates towards zero, so instants in the same millisecond share one key. Adding 0.0 makes -0.0 positive.
+	let epoch_ms = epoch_ms.trunc() + 0.0;
+	Some(InternHandle::from_raw(ISO_TIMESTAMP_CACHE.with(|c| {
+		c.cache_with(epoch_ms.into(), |_| Date::new(&JsValue::from_f64(epoch_ms)).to_iso_string().into())
+	})))
+}
@@ -0,0 +1,142 @@
+use crate::{BitwiseFloat, Cacher};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use wasm_bindgen::prelude::*;
+
+/// An interner which owns its values, rather than storing them for the rest of the thread like ```js_intern!``` does.
+/// Values are freed when the interner is dropped, and are only de-duplicated within the same interner.
+/// This is useful to scope a set of values to some part of a program.
+///
+/// To configure an interner, use ```Interner::builder()```. The keys are hashed with ```S```, which is the standard
+/// ```RandomState``` unless another ```BuildHasher``` is set with ```InternerBuilder::hasher```, eg: one suited to the keys.
+#[derive(Default)]
+pub struct Interner<S = RandomState> {
+	strings: Cacher<&'static str, S>,
+	numbers: Cacher<BitwiseFloat, S>,
+	bools: Cacher<bool, S>,
+	factory: Option<fn(InternKey) -> JsValue>,
+}
+
+// Caches don't free their values when dropped with the leak-forever feature, but an interner's values are still scoped to it.
+#[cfg(feature = "leak-forever")]
+impl<S> Drop for Interner<S> {
+	fn drop(&mut self) {
+		self.strings.clear();
+		self.numbers.clear();
+		self.bools.clear();
+	}
+}
+
+/// A value to be interned by an ```Interner```, as passed to the factory set with ```InternerBuilder::factory```.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InternKey {
+	Str(&'static str),
+	F64(f64),
+	Bool(bool),
+}
+
+impl Interner {
+	/// Creates an empty interner.
+	pub fn new() -> Interner {
+		Interner::default()
+	}
+
+	/// Starts configuring a new interner.
+	pub fn builder() -> InternerBuilder {
+		InternerBuilder::default()
+	}
+}
+
+impl<S: BuildHasher> Interner<S> {
+	/// Returns the string, converting it the first time it is seen by this interner.
+	pub fn intern_str(&self, s: &'static str) -> &JsValue {
+		self.lend(self.strings.cache_with(s, |&s| self.convert(InternKey::Str(s))))
+	}
+
+	/// Returns the number, converting it the first time it is seen by this interner.
+	/// Numbers are compared by their bit patterns, the same as ```js_intern!``` does for floats.
+	pub fn intern_f64(&self, value: f64) -> &JsValue {
+		self.lend(self.numbers.cache_with(value.into(), |_| self.convert(InternKey::F64(value))))
+	}
+
+	/// Returns the bool, converting it the first time it is seen by this interner.
+	pub fn intern_bool(&self, value: bool) -> &JsValue {
+		self.lend(self.bools.cache_with(value, |_| self.convert(InternKey::Bool(value))))
+	}
+
+	/// Returns each value interned by this interner along with its key, eg: to snapshot or serialize its contents.
+	/// Strings come first, then numbers, then bools, and each type is in no particular order. The entries are
+	/// copied out when this is called, so values interned while iterating are not included.
+	pub fn iter(&self) -> impl Iterator<Item = (InternKey, &JsValue)> {
+		let strings = self.strings.iter().map(|(s, value)| (InternKey::Str(s), value));
+		let numbers = self.numbers.iter().map(|(number, value)| (InternKey::F64(number.into()), value));
+		let bools = self.bools.iter().map(|(b, value)| (InternKey::Bool(b), value));
+		strings.chain(numbers).chain(bools).map(move |(key, value)| (key, self.lend(value)))
+	}
+
+	/// Returns the number of values of each type which can be interned without reallocating.
+	pub fn capacity(&self) -> usize {
+		self.strings.capacity().min(self.numbers.capacity()).min(self.bools.capacity())
+	}
+
+	fn convert(&self, key: InternKey) -> JsValue {
+		if let Some(factory) = self.factory {
+			return factory(key);
+		}
+		match key {
+			InternKey::Str(s) => JsValue::from_str(s),
+			InternKey::F64(value) => JsValue::from_f64(value),
+			InternKey::Bool(value) => JsValue::from_bool(value),
+		}
+	}
+
+	// The boxed values are never removed, so they live as long as the interner.
+	fn lend(&self, value: *mut JsValue) -> &JsValue {
+		unsafe { &*value }
+	}
+}
+
+/// Configures an ```Interner```. Create one with ```Interner::builder()```.
+///
+/// The load factor is not configurable, since the standard ```HashMap``` used by the interner does not expose one.
+#[derive(Default)]
+pub struct InternerBuilder<S = RandomState> {
+	capacity: usize,
+	factory: Option<fn(InternKey) -> JsValue>,
+	hasher: S,
+}
+
+impl<S: BuildHasher + Clone> InternerBuilder<S> {
+	/// Sets the number of values of each type which can be interned before the interner needs to reallocate.
+	pub fn capacity(mut self, capacity: usize) -> InternerBuilder<S> {
+		self.capacity = capacity;
+		self
+	}
+
+	/// Sets the function which converts each value the first time it is seen, in place of the usual conversion.
+	/// This is intended for tests, which can use it to produce predictable values or to count conversions
+	/// without a JavaScript engine, eg: by returning ```JsValue::UNDEFINED```.
+	pub fn factory(mut self, factory: fn(InternKey) -> JsValue) -> InternerBuilder<S> {
+		self.factory = Some(factory);
+		self
+	}
+
+	/// Sets the hasher used for the keys of each type, in place of the standard ```RandomState```.
+	pub fn hasher<H: BuildHasher + Clone>(self, hasher: H) -> InternerBuilder<H> {
+		InternerBuilder {
+			capacity: self.capacity,
+			factory: self.factory,
+			hasher,
+		}
+	}
+
+	/// Creates the interner.
+	pub fn build(self) -> Interner<S> {
+		Interner {
+			strings: Cacher::with_capacity_and_hasher(self.capacity, self.hasher.clone()),
+			numbers: Cacher::with_capacity_and_hasher(self.capacity, self.hasher.clone()),
+			bools: Cacher::with_capacity_and_hasher(self.capacity, self.hasher),
+			factory: self.factory,
+		}
+	}
+}
@@ -1,48 +1,500 @@
+// Without the strings or numbers features, and with bools as singletons or not at all, none of the caches are used,
+// but the crate still builds.
+#![cfg_attr(
+	not(any(feature = "strings", feature = "numbers", all(feature = "bools", not(feature = "small")))),
+	allow(dead_code, unused_imports)
+)]
+
+use std::borrow::Borrow;
+#[cfg(feature = "strings")]
+use std::borrow::Cow;
+#[cfg(feature = "numbers")]
+use std::cmp::Ordering;
+use std::collections::hash_map::{Entry, RandomState};
 use std::collections::{HashMap};
+#[cfg(feature = "strings")]
+use std::collections::VecDeque;
+#[cfg(feature = "strings")]
+use std::error::Error;
+#[cfg(feature = "strings")]
+use std::fmt;
 use wasm_bindgen::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::mem::transmute;
-use std::hash::{Hash};
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+#[cfg(feature = "numbers")]
+use std::num::Wrapping;
+use std::ops::Deref;
+use std::ptr;
+use std::thread::LocalKey;
+
+mod arrays;
+mod collections;
+mod debug;
+mod format;
+mod interner;
+#[cfg(feature = "numbers")]
+mod numbers;
+#[cfg(feature = "strings")]
+mod strings;
+pub use arrays::*;
+pub use collections::*;
+pub use debug::*;
+pub use format::*;
+pub use interner::*;
+#[cfg(feature = "numbers")]
+pub use numbers::*;
+#[cfg(feature = "strings")]
+pub use strings::*;
 
 thread_local!(
+	// Incremented by reset_interner, so that each use of js_intern! knows to look its value up again.
+	static EPOCH: Cell<u64> = const { Cell::new(1) };
+	#[cfg(feature = "numbers")]
 	static FLOAT_CACHE: Cacher<BitwiseFloat> = Cacher::new();
-	static STRING_CACHE: Cacher<&'static str> = Cacher::new();
+	#[cfg(feature = "numbers")]
+	static NAN: JsValue = JsValue::from_f64(f64::NAN);
+	// The most common constants in math code, which skip hashing like NaN.
+	#[cfg(feature = "numbers")]
+	static ZERO: JsValue = JsValue::from_f64(0.0);
+	#[cfg(feature = "numbers")]
+	static ONE: JsValue = JsValue::from_f64(1.0);
+	#[cfg(feature = "numbers")]
+	static NEG_ONE: JsValue = JsValue::from_f64(-1.0);
+	#[cfg(feature = "numbers")]
+	static CANONICALIZE_NAN: Cell<bool> = const { Cell::new(false) };
+	#[cfg(feature = "numbers")]
+	static COLLAPSE_NEG_ZERO: Cell<bool> = const { Cell::new(false) };
+	static UNDEFINED: JsValue = const { JsValue::UNDEFINED };
+	static NULL: JsValue = const { JsValue::NULL };
+	#[cfg(feature = "numbers")]
+	static SMALL_INT_TABLE: RefCell<Vec<Option<Box<[*mut JsValue; 256]>>>> = RefCell::default();
+	#[cfg(feature = "strings")]
+	static STRING_CACHE: Cacher<Cow<'static, str>> = Cacher::new();
+	#[cfg(feature = "strings")]
+	static ON_STRING_MISS: Cell<Option<fn(&str)>> = const { Cell::new(None) };
+	// The strings which were added to the string cache while a cache was borrowed, to be passed to the
+	// function set by on_string_miss once every cache has been returned.
+	#[cfg(feature = "strings")]
+	static PENDING_MISSES: RefCell<VecDeque<Cow<'static, str>>> = RefCell::default();
+	// The number of caches on this thread which are borrowed to add a value.
+	static CACHES_ADDING: Cell<usize> = const { Cell::new(0) };
+	static ON_CLEAR: Cell<Option<fn()>> = const { Cell::new(None) };
+	// The number of strings in the string cache, which can't be checked while a string is being added.
+	#[cfg(feature = "strings")]
+	static STRING_COUNT: Cell<usize> = const { Cell::new(0) };
+	#[cfg(feature = "strings")]
+	static STRING_LIMIT: Cell<Option<usize>> = const { Cell::new(None) };
+	#[cfg(all(feature = "bloom", feature = "strings"))]
+	static STRING_BLOOM: RefCell<[u64; BLOOM_WORDS]> = const { RefCell::new([0; BLOOM_WORDS]) };
+	#[cfg(all(feature = "bools", not(feature = "small")))]
 	static BOOL_CACHE: Cacher<bool> = Cacher::new(); // TODO: This is a bit overkill.
+	#[cfg(all(feature = "bools", feature = "small"))]
+	static TRUE: JsValue = const { JsValue::from_bool(true) };
+	#[cfg(all(feature = "bools", feature = "small"))]
+	static FALSE: JsValue = const { JsValue::from_bool(false) };
 	// TODO: Include None. The first thought would be for Option<!> if that compiles with a simple js_intern!(None). wasm-bindgen treats this as undefined rather than null, so then should we.
 );
 
 #[doc(hidden)]
 /// This is a private trait and not meant to be used.
+#[diagnostic::on_unimplemented(
+	message = "js_intern! can't intern a `{Self}`",
+	label = "not a supported type, or the feature for it is disabled",
+	note = "numbers need the `numbers` feature of js-intern, bools need `bools` and strings need `strings`, which are all enabled by default"
+)]
 pub trait CacheJsIntern__ {
 	fn cache_js_intern__(self) -> *mut JsValue;
 }
 
+#[doc(hidden)]
+/// This is a private function used by js_intern! and not meant to be used.
+/// Unlike calling the method, this names the type which can't be interned when its feature is disabled.
+#[inline]
+pub fn cache_js_intern__<T: CacheJsIntern__>(value: T) -> *mut JsValue {
+	value.cache_js_intern__()
+}
+
+/// Separates the key a value is cached by from its conversion to a ```JsValue```, so that the
+/// key can be cheap to hash while the conversion is deferred until the value is missing from the cache.
+#[cfg(any(feature = "numbers", all(feature = "bools", not(feature = "small"))))]
+trait IntoInternKey {
+	type Key: Eq + Hash;
+	fn key(&self) -> Self::Key;
+	fn to_js(self) -> JsValue;
+}
+
+#[cfg(feature = "numbers")]
+impl IntoInternKey for f64 {
+	type Key = BitwiseFloat;
+	fn key(&self) -> BitwiseFloat {
+		(*self).into()
+	}
+	fn to_js(self) -> JsValue {
+		JsValue::from_f64(self)
+	}
+}
+
+#[cfg(all(feature = "bools", not(feature = "small")))]
+impl IntoInternKey for bool {
+	type Key = bool;
+	fn key(&self) -> bool {
+		*self
+	}
+	fn to_js(self) -> JsValue {
+		JsValue::from_bool(self)
+	}
+}
+
+#[cfg(feature = "numbers")]
 impl CacheJsIntern__ for f64 {
 	fn cache_js_intern__(self) -> *mut JsValue {
+		let value = canonicalize_float(self);
+		if let Some(value) = float_singleton(value) {
+			return value;
+		}
 		FLOAT_CACHE.with(|c| {
-			c.cache(self.into())
+			c.cache_keyed(value)
 		})
 	}
 }
 
+/// Applies the settings of ```set_canonicalize_nan``` and ```set_collapse_neg_zero```.
+#[cfg(feature = "numbers")]
+fn canonicalize_float(value: f64) -> f64 {
+	if value.is_nan() && CANONICALIZE_NAN.with(|c| c.get()) {
+		f64::NAN
+	} else if value.to_bits() == (-0.0f64).to_bits() && COLLAPSE_NEG_ZERO.with(|c| c.get()) {
+		0.0
+	} else {
+		value
+	}
+}
+
+/// NaN, ```0.0```, ```1.0``` and ```-1.0``` get a fast path to skip hashing, so they are never in the float cache.
+/// Only the exact bit patterns do, since other NaN payloads and ```-0.0``` are distinct values for the purposes
+/// of this crate (see BitwiseFloat).
+#[cfg(feature = "numbers")]
+fn float_singleton(value: f64) -> Option<*mut JsValue> {
+	let bits = value.to_bits();
+	if bits == f64::NAN.to_bits() {
+		Some(singleton(&NAN))
+	} else if bits == 0.0f64.to_bits() {
+		Some(singleton(&ZERO))
+	} else if bits == 1.0f64.to_bits() {
+		Some(singleton(&ONE))
+	} else if bits == (-1.0f64).to_bits() {
+		Some(singleton(&NEG_ONE))
+	} else {
+		None
+	}
+}
+
+#[cfg(feature = "strings")]
 impl CacheJsIntern__ for &'static str {
 	fn cache_js_intern__(self) -> *mut JsValue {
-		STRING_CACHE.with(|c| {
-			c.cache(self)
+		match STRING_CACHE.with(|c| c.get(self)) {
+			Some(value) => value,
+			None => cache_cow_str(Cow::Borrowed(self)),
+		}
+	}
+}
+
+#[cfg(feature = "strings")]
+// The string is looked up by reference, and only moved into the cache when it is missing,
+// where it is kept rather than leaked.
+impl CacheJsIntern__ for String {
+	fn cache_js_intern__(self) -> *mut JsValue {
+		match STRING_CACHE.with(|c| c.get(&self[..])) {
+			Some(value) => value,
+			None => cache_cow_str(Cow::Owned(self)),
+		}
+	}
+}
+
+/// Converts a string which is missing from the string cache.
+#[cfg(feature = "strings")]
+fn convert_str(s: &str) -> JsValue {
+	let count = STRING_COUNT.with(|c| c.get());
+	if let Some(limit) = STRING_LIMIT.with(|l| l.get()) {
+		debug_assert!(count < limit, "interning {:?} exceeds the limit of {} strings", s, limit);
+	}
+	STRING_COUNT.with(|c| c.set(count + 1));
+	#[cfg(feature = "bloom")]
+	STRING_BLOOM.with(|b| {
+		let mut bits = b.borrow_mut();
+		for &bit in bloom_bits(s).iter() {
+			bits[bit / 64] |= 1 << (bit % 64);
+		}
+	});
+	JsValue::from_str(s)
+}
+
+/// The error returned by ```try_intern_str``` when the string cache is full.
+#[cfg(feature = "strings")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CacheFull {
+	/// The limit which was reached, as set with ```set_string_cache_limit```.
+	pub limit: usize,
+}
+
+#[cfg(feature = "strings")]
+impl fmt::Display for CacheFull {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "the string cache is full, with a limit of {} strings", self.limit)
+	}
+}
+
+#[cfg(feature = "strings")]
+impl Error for CacheFull {}
+
+/// Limits the number of distinct strings which may be interned on this thread, or removes the limit with ```None```.
+/// This catches bugs where strings are interned without bound, such as interning user input.
+/// Strings which were already interned can still be used once the limit is reached.
+///
+/// Use ```try_intern_str``` to handle reaching the limit. Otherwise, going over it panics in builds with
+/// debug assertions enabled, and is allowed in other builds.
+#[cfg(feature = "strings")]
+pub fn set_string_cache_limit(limit: Option<usize>) {
+	STRING_LIMIT.with(|l| l.set(limit));
+}
+
+/// Sets whether NaNs with any payload are interned as the one canonical ```f64::NAN``` on this thread, rather than as
+/// distinct values, which is the default. This only affects values interned afterwards: NaNs which were already interned
+/// stay in the cache as they are, and each use of ```js_intern!``` which already got its value keeps it.
+#[cfg(feature = "numbers")]
+pub fn set_canonicalize_nan(canonicalize: bool) {
+	CANONICALIZE_NAN.with(|c| c.set(canonicalize));
+}
+
+/// Sets whether ```-0.0``` is interned as ```0.0``` on this thread, rather than as a distinct value, which is the default.
+/// Like ```set_canonicalize_nan```, this only affects values interned afterwards, and doesn't rewrite existing entries.
+#[cfg(feature = "numbers")]
+pub fn set_collapse_neg_zero(collapse: bool) {
+	COLLAPSE_NEG_ZERO.with(|c| c.set(collapse));
+}
+
+/// Interns a string, unless it would go over the limit set with ```set_string_cache_limit```.
+/// The value is the same instance as ```js_intern!(s)```.
+#[cfg(feature = "strings")]
+pub fn try_intern_str(s: &'static str) -> Result<InternHandle, CacheFull> {
+	if let Some(value) = STRING_CACHE.with(|c| c.get(s)) {
+		return Ok(InternHandle::from_raw(value));
+	}
+	if let Some(limit) = STRING_LIMIT.with(|l| l.get()) {
+		if STRING_COUNT.with(|c| c.get()) >= limit {
+			return Err(CacheFull { limit });
+		}
+	}
+	Ok(InternHandle::from_raw(s.cache_js_intern__()))
+}
+
+/// Returns the string if it has already been interned on this thread, without interning it otherwise.
+/// The value is the same instance as ```js_intern!(s)```.
+///
+/// With the ```bloom``` feature, most strings which have not been interned are rejected by a Bloom filter
+/// without probing the string cache. Strings which have been interned always pass the filter.
+#[cfg(feature = "strings")]
+pub fn get_interned_str(s: &str) -> Option<InternHandle> {
+	#[cfg(feature = "bloom")]
+	{
+		let maybe_interned = STRING_BLOOM.with(|b| {
+			let bits = b.borrow();
+			bloom_bits(s).iter().all(|&bit| bits[bit / 64] & (1 << (bit % 64)) != 0)
+		});
+		if !maybe_interned {
+			return None;
+		}
+	}
+	STRING_CACHE.with(|c| c.get(s)).map(InternHandle::from_raw)
+}
+
+/// Returns the interned instance equal to a value which came back from JavaScript, eg: as the argument of a callback,
+/// where it is a different ```JsValue``` even if it was passed out as an interned one. Strings and numbers are only found
+/// if they have already been interned on this thread, and are not interned otherwise. Bools, ```undefined``` and
+/// ```null``` are always found, and anything else, such as an object, is ```None```.
+pub fn reintern_from_js(value: &JsValue) -> Option<InternHandle> {
+	#[cfg(feature = "strings")]
+	{
+		if let Some(s) = value.as_string() {
+			return get_interned_str(&s);
+		}
+	}
+	#[cfg(feature = "numbers")]
+	{
+		if let Some(number) = value.as_f64() {
+			if number.is_nan() {
+				return Some(InternHandle::from_raw(singleton(&NAN)));
+			}
+			let number = canonicalize_float(number);
+			if let Some(value) = float_singleton(number) {
+				return Some(InternHandle::from_raw(value));
+			}
+			return FLOAT_CACHE.with(|c| c.get(&BitwiseFloat::from(number))).map(InternHandle::from_raw);
+		}
+	}
+	#[cfg(feature = "bools")]
+	{
+		if let Some(b) = value.as_bool() {
+			return Some(intern_bool(b));
+		}
+	}
+	if value.is_undefined() {
+		return Some(InternHandle::from_raw(singleton(&UNDEFINED)));
+	}
+	if value.is_null() {
+		return Some(InternHandle::from_raw(singleton(&NULL)));
+	}
+	None
+}
+
+// 64Ki bits, which keeps false positives under 1% for up to around 3,000 strings.
+#[cfg(all(feature = "bloom", feature = "strings"))]
+const BLOOM_WORDS: usize = 1024;
+
+/// Returns the bits of the Bloom filter which are set for a string. FNV-1a is used rather than the
+/// string cache's hasher, since it is much cheaper for the short strings which are typically interned.
+#[cfg(all(feature = "bloom", feature = "strings"))]
+fn bloom_bits(s: &str) -> [usize; 2] {
+	let hash = fnv1a(s);
+	let mask = BLOOM_WORDS * 64 - 1;
+	[hash as usize & mask, (hash >> 32) as usize & mask]
+}
+
+/// The 64 bit FNV-1a hash of a string, which unlike the standard hasher is the same in every program and release.
+#[cfg(feature = "strings")]
+fn fnv1a(s: &str) -> u64 {
+	let mut hash = 0xcbf2_9ce4_8422_2325u64;
+	for &byte in s.as_bytes() {
+		hash ^= byte as u64;
+		hash = hash.wrapping_mul(0x100_0000_01b3);
+	}
+	hash
+}
+
+/// Sets a function to be called with each string the first time it is interned on this thread, or unsets it with ```None```.
+/// This is meant for instrumentation, such as logging which strings are new. When no function is set,
+/// the only cost is checking for one when a string is missing from the cache.
+///
+/// The function is called once no cache is in the middle of adding a value, such as when a string is interned while
+/// building an array of strings, so it may intern any values itself.
+#[cfg(feature = "strings")]
+pub fn on_string_miss(callback: Option<fn(&str)>) {
+	ON_STRING_MISS.with(|c| c.set(callback));
+}
+
+/// Interns a string which may or may not be ```'static```, sharing the string cache.
+/// Owned strings are kept by the cache the first time their contents are seen, and dropped otherwise.
+#[cfg(feature = "strings")]
+fn cache_cow_str(s: Cow<'static, str>) -> *mut JsValue {
+	STRING_CACHE.with(|c| {
+		c.cache_with(s, |s| {
+			if ON_STRING_MISS.with(|c| c.get()).is_some() {
+				PENDING_MISSES.with(|p| p.borrow_mut().push_back(s.clone()));
+			}
+			convert_str(s)
 		})
+	})
+}
+
+/// Passes each pending string to the function set by on_string_miss, which may intern more of them.
+fn report_string_misses() {
+	#[cfg(feature = "strings")]
+	while let Some(s) = PENDING_MISSES.with(|p| p.borrow_mut().pop_front()) {
+		if let Some(callback) = ON_STRING_MISS.with(|c| c.get()) {
+			callback(&s);
+		}
 	}
 }
 
+/// Counts a cache as borrowed to add a value for as long as it lives, including while unwinding from a conversion.
+struct AddingValue;
+
+impl AddingValue {
+	fn start() -> AddingValue {
+		CACHES_ADDING.with(|c| c.set(c.get() + 1));
+		AddingValue
+	}
+
+	/// Returns whether no other cache is still adding a value.
+	fn finish(self) -> bool {
+		drop(self);
+		CACHES_ADDING.with(|c| c.get()) == 0
+	}
+}
+
+impl Drop for AddingValue {
+	fn drop(&mut self) {
+		CACHES_ADDING.with(|c| c.set(c.get() - 1));
+	}
+}
+
+#[cfg(all(feature = "bools", not(feature = "small")))]
 impl CacheJsIntern__ for bool {
 	fn cache_js_intern__(self) -> *mut JsValue {
 		BOOL_CACHE.with(|c| {
-			c.cache(self)
+			c.cache_keyed(self)
 		})
 	}
 }
 
+// There are only two bools, so there is no need for a map.
+#[cfg(all(feature = "bools", feature = "small"))]
+impl CacheJsIntern__ for bool {
+	fn cache_js_intern__(self) -> *mut JsValue {
+		singleton(if self { &TRUE } else { &FALSE })
+	}
+}
+
+/// Interns a bool which is only known at runtime, such as a snapshot of an ```AtomicBool```.
+/// This is the same instance as ```js_intern!(true)``` or ```js_intern!(false)```, without passing an expression to ```js_intern!```.
+#[cfg(feature = "bools")]
+pub fn intern_bool(value: bool) -> InternHandle {
+	InternHandle::from_raw(value.cache_js_intern__())
+}
+
+/// Interns a bool and its negation, as ```(intern_bool(value), intern_bool(!value))```, for code which needs both.
+#[cfg(feature = "bools")]
+pub fn intern_bool_pair(value: bool) -> (InternHandle, InternHandle) {
+	(intern_bool(value), intern_bool(!value))
+}
+
+/// Interns the state of a checkbox which may be indeterminate, as ```true``` when checked, ```false``` when unchecked,
+/// and ```null``` when indeterminate with ```None```. The bools are the same instances as ```js_intern!```, and ```null``` is a singleton.
+#[cfg(feature = "bools")]
+pub fn intern_tristate(state: Option<bool>) -> InternHandle {
+	match state {
+		Some(value) => intern_bool(value),
+		None => InternHandle::from_raw(singleton(&NULL)),
+	}
+}
+
+// Singletons are never written to through the pointer, it's only *mut to fit the trait.
+fn singleton(key: &'static LocalKey<JsValue>) -> *mut JsValue {
+	key.with(|v| v as *const JsValue as *mut JsValue)
+}
+
+#[cfg(debug_assertions)]
+fn is_singleton(value: *const JsValue) -> bool {
+	#[cfg(all(feature = "bools", feature = "small"))]
+	{
+		if TRUE.with(|v| ptr::eq(v, value)) || FALSE.with(|v| ptr::eq(v, value)) {
+			return true;
+		}
+	}
+	#[cfg(feature = "numbers")]
+	{
+		if [&NAN, &ZERO, &ONE, &NEG_ONE].iter().any(|key| key.with(|v| ptr::eq(v, value))) {
+			return true;
+		}
+	}
+	UNDEFINED.with(|v| ptr::eq(v, value)) || NULL.with(|v| ptr::eq(v, value))
+}
+
 macro_rules! CacheForT64 {
 	($t:ty) => {
+		#[cfg(feature = "numbers")]
 		impl CacheJsIntern__ for $t {
 			fn cache_js_intern__(self) -> *mut JsValue {
 				(self as f64).cache_js_intern__()
@@ -51,20 +503,159 @@ macro_rules! CacheForT64 {
 	};
 }
 
-CacheForT64!(i8);
-CacheForT64!(i16);
 CacheForT64!(i32);
-CacheForT64!(u8);
-CacheForT64!(u16);
 CacheForT64!(u32);
 CacheForT64!(f32);
 
-struct Cacher<T: Eq + Hash> {
-	inner: RefCell<HashMap<T, *mut JsValue>>
+macro_rules! CacheForSmallInt {
+	($t:ty) => {
+		#[cfg(feature = "numbers")]
+		impl CacheJsIntern__ for $t {
+			fn cache_js_intern__(self) -> *mut JsValue {
+				cache_small_int(self.into())
+			}
+		}
+	};
+}
+
+CacheForSmallInt!(i8);
+CacheForSmallInt!(i16);
+CacheForSmallInt!(u8);
+CacheForSmallInt!(u16);
+
+macro_rules! CacheForWrapping {
+	($t:ty) => {
+		#[cfg(feature = "numbers")]
+		impl CacheJsIntern__ for Wrapping<$t> {
+			fn cache_js_intern__(self) -> *mut JsValue {
+				self.0.cache_js_intern__()
+			}
+		}
+	};
+}
+
+CacheForWrapping!(i8);
+CacheForWrapping!(i16);
+CacheForWrapping!(i32);
+CacheForWrapping!(u8);
+CacheForWrapping!(u16);
+CacheForWrapping!(u32);
+
+// Comparisons are -1, 0 or 1, as in JavaScript comparator functions.
+#[cfg(feature = "numbers")]
+impl CacheJsIntern__ for Ordering {
+	fn cache_js_intern__(self) -> *mut JsValue {
+		cache_small_int(self as i32)
+	}
+}
+
+// Like wasm-bindgen, None is undefined rather than null. Characters share the string cache,
+// so js_intern!(Some('a')) is the same instance as js_intern!("a").
+#[cfg(feature = "strings")]
+impl CacheJsIntern__ for Option<char> {
+	fn cache_js_intern__(self) -> *mut JsValue {
+		match self {
+			Some(c) => cache_cow_str(Cow::Owned(c.to_string())),
+			None => singleton(&UNDEFINED),
+		}
+	}
+}
+
+// Covers every value of i8, i16, u8 and u16.
+#[cfg(feature = "numbers")]
+const SMALL_INT_MIN: i32 = i16::MIN as i32;
+#[cfg(feature = "numbers")]
+const SMALL_INT_CHUNKS: usize = (u16::MAX as i32 - SMALL_INT_MIN + 1) as usize / 256;
+
+/// Integers of up to 16 bits have a small enough range to be looked up by indexing rather than hashing.
+/// The table is split into chunks of 256 which are only allocated when first used, so a program which
+/// only interns a few small numbers doesn't pay for the whole range. The table doesn't own the values,
+/// it points into the float cache, so small ints are the same instance as the equivalent f64.
+#[cfg(feature = "numbers")]
+fn cache_small_int(value: i32) -> *mut JsValue {
+	let index = (value - SMALL_INT_MIN) as usize;
+	SMALL_INT_TABLE.with(|t| {
+		let mut chunks = t.borrow_mut();
+		if chunks.is_empty() {
+			chunks.resize_with(SMALL_INT_CHUNKS, || None);
+		}
+		let chunk = chunks[index >> 8].get_or_insert_with(|| Box::new([ptr::null_mut(); 256]));
+		let slot = &mut chunk[index & 0xff];
+		if slot.is_null() {
+			*slot = f64::from(value).cache_js_intern__();
+		}
+		*slot
+	})
+}
+
+struct Cacher<T: Eq + Hash, S = RandomState> {
+	inner: RefCell<HashMap<T, *mut JsValue, S>>
 }
 
 impl<T: Eq + Hash> Cacher<T> {
 	fn new() -> Cacher<T> {
+		Cacher::default()
+	}
+}
+
+impl<T: Eq + Hash, S: BuildHasher> Cacher<T, S> {
+	fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Cacher<T, S> {
+		Cacher {
+			inner: RefCell::new(HashMap::with_capacity_and_hasher(capacity, hasher))
+		}
+	}
+
+	fn capacity(&self) -> usize {
+		self.inner.borrow().capacity()
+	}
+
+	fn len(&self) -> usize {
+		self.inner.borrow().len()
+	}
+
+	/// Copies out each key and its value, in no particular order, so that the cache can be added to while iterating.
+	fn iter(&self) -> std::vec::IntoIter<(T, *mut JsValue)> where T: Clone {
+		self.inner.borrow().iter().map(|(key, &value)| (key.clone(), value)).collect::<Vec<_>>().into_iter()
+	}
+
+	#[cfg(feature = "strings")]
+	/// Maps up to ```count``` of the keys, in no particular order.
+	fn sample_keys<R>(&self, count: usize, f: impl Fn(&T) -> R) -> Vec<R> {
+		self.inner.borrow().keys().take(count).map(f).collect()
+	}
+
+	#[cfg(feature = "strings")]
+	/// Frees every value except the ```keep``` with the highest scores, and returns how many were freed.
+	/// Any references lent out from the cache to the freed values become invalid.
+	fn retain_top_by<K: Ord>(&self, keep: usize, score: impl Fn(&T) -> K) -> usize {
+		let mut map = self.inner.borrow_mut();
+		if map.len() <= keep {
+			return 0;
+		}
+		let mut entries: Vec<(T, *mut JsValue)> = map.drain().collect();
+		entries.sort_by_cached_key(|(key, _value)| std::cmp::Reverse(score(key)));
+		let evicted = entries.split_off(keep);
+		map.extend(entries);
+		for &(_, value) in evicted.iter() {
+			debug::forget_handed_out(value);
+			unsafe { drop(Box::from_raw(value)); }
+		}
+		evicted.len()
+	}
+}
+
+impl<T: Eq + Hash, S> Cacher<T, S> {
+	/// Frees every value, leaving the cache empty. Any references lent out from it become invalid.
+	fn clear(&self) {
+		for (_key, value) in self.inner.borrow_mut().drain() {
+			debug::forget_handed_out(value);
+			unsafe { drop(Box::from_raw(value)); }
+		}
+	}
+}
+
+impl<T: Eq + Hash, S: Default> Default for Cacher<T, S> {
+	fn default() -> Cacher<T, S> {
 		Cacher {
 			inner: RefCell::default()
 		}
@@ -74,28 +665,244 @@ impl<T: Eq + Hash> Cacher<T> {
 // Implementing Drop is probably overkill, since in eg: a browser, there
 // is only one JavaScript engine. But, I can imagine a system that had
 // multiple JavaScript engines. Perhaps one per thread.
-impl<T: Eq + Hash> Drop for Cacher<T> {
+// The leak-forever feature leaves it out, for programs whose threads live as long as they do.
+#[cfg(not(feature = "leak-forever"))]
+impl<T: Eq + Hash, S> Drop for Cacher<T, S> {
 	fn drop(&mut self) {
 		// Ensure we free all the heap allocations from our boxes,
 		// and drop the js values contained in them.
 		for (_key, value) in self.inner.borrow_mut().drain() {
-			unsafe { Box::from_raw(value); }
+			debug::forget_handed_out(value);
+			unsafe { drop(Box::from_raw(value)); }
 		}
 	}
 }
 
-impl<T: Into<JsValue> + Eq + Hash + Copy> Cacher<T> {
-	fn cache(&self, value: T) -> *mut JsValue {
+impl<T: Eq + Hash, S: BuildHasher> Cacher<T, S> {
+	fn cache_with(&self, key: T, convert: impl FnOnce(&T) -> JsValue) -> *mut JsValue {
 		let mut map = self.inner.borrow_mut();
 		// Note that if Cacher is ever used outside this crate, we would need to make
-		// this function re-entrant, since T::into<JsValue> could execute arbitrary
+		// this function re-entrant, since convert could execute arbitrary
 		// code, this could get called by it, and the borrow_mut() would panic.
-		// For now, it's only used on types for which this is not a problem.
+		// For now, it's only used on conversions for which this is not a problem.
+		match map.entry(key) {
+			Entry::Occupied(entry) => *entry.get(),
+			Entry::Vacant(entry) => {
+				let adding = AddingValue::start();
+				let value = Box::into_raw(Box::new(convert(entry.key())));
+				debug::record_handed_out(value);
+				entry.insert(value);
+				// Functions which are called once the value is added may use this cache too.
+				drop(map);
+				if adding.finish() {
+					report_string_misses();
+				}
+				value
+			}
+		}
+	}
+}
 
-		*map.entry(value).or_insert_with(move || {
-			let js_value: JsValue = value.into();
-			Box::into_raw(Box::new(js_value))
-		})
+impl<T: Eq + Hash, S: BuildHasher> Cacher<T, S> {
+	fn get<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> Option<*mut JsValue> where T: Borrow<Q> {
+		self.inner.borrow().get(key).copied()
+	}
+}
+
+#[cfg(any(feature = "numbers", all(feature = "bools", not(feature = "small"))))]
+impl<T: Eq + Hash, S: BuildHasher> Cacher<T, S> {
+	fn cache_keyed<V: IntoInternKey<Key = T>>(&self, value: V) -> *mut JsValue {
+		let key = value.key();
+		if let Some(cached) = self.get(&key) {
+			return cached;
+		}
+		self.cache_with(key, move |_| value.to_js())
+	}
+}
+
+/// Lends out a value owned by a Cacher for the rest of the thread, or until reset_interner is called.
+/// See the note on safety in js_intern!, which applies here as well.
+fn as_static(value: *mut JsValue) -> &'static JsValue {
+	unsafe { &*value }
+}
+
+/// Returns a number which increases each time ```reset_interner``` is called on this thread, or the string cache is
+/// cleared by ```clear_string_cache_if_over```, ```evict_strings_by``` or ```reload_strings```. Code which holds
+/// on to interned values can record this when it gets them, and get them again if it has changed since.
+pub fn intern_epoch() -> u64 {
+	EPOCH.with(|e| e.get())
+}
+
+/// Frees every value interned on this thread, so that long running programs such as embedded
+/// JavaScript engines can start over without waiting for the thread to exit. Values are interned
+/// again the next time they are used, including by each use of ```js_intern!```.
+///
+/// Values owned by an ```Interner``` are not affected.
+///
+/// # Safety
+/// Every reference previously returned by this crate on this thread is invalidated, including those
+/// from ```js_intern!``` and each ```InternHandle```. None of them may be used after calling this.
+/// Code which can't tell whether a reset happened can check ```intern_epoch```.
+pub unsafe fn reset_interner() {
+	arrays::clear_caches();
+	collections::clear_caches();
+	format::clear_caches();
+	#[cfg(feature = "numbers")]
+	{
+		numbers::clear_caches();
+		// The small int table only points into the float cache, so it's emptied rather than freed.
+		SMALL_INT_TABLE.with(|t| t.borrow_mut().clear());
+		FLOAT_CACHE.with(|c| c.clear());
+	}
+	#[cfg(feature = "strings")]
+	{
+		strings::clear_caches();
+		clear_string_cache();
+	}
+	#[cfg(all(feature = "bools", not(feature = "small")))]
+	BOOL_CACHE.with(|c| c.clear());
+	EPOCH.with(|e| e.set(e.get() + 1));
+	notify_clear();
+}
+
+/// Frees every interned string on this thread if there are more than ```n``` of them, and returns whether it did.
+/// This is cheap when there are ```n``` or fewer, so long running programs can call it every frame to keep the
+/// string cache from growing without bound. Values of other types are kept.
+///
+/// # Safety
+/// When this returns ```true```, every reference to an interned string previously returned by this crate on this thread
+/// is invalidated, as by ```reset_interner```, along with those from ```intern_kv``` and ```js_intern_object!```,
+/// which are keyed by the addresses of strings. Like ```reset_interner```, this increases ```intern_epoch```.
+#[cfg(feature = "strings")]
+pub unsafe fn clear_string_cache_if_over(n: usize) -> bool {
+	if STRING_CACHE.with(|c| c.len()) <= n {
+		return false;
+	}
+	collections::clear_string_pointers();
+	strings::clear_string_pointers();
+	clear_string_cache();
+	EPOCH.with(|e| e.set(e.get() + 1));
+	notify_clear();
+	true
+}
+
+/// Frees every interned string on this thread except the ```keep``` with the highest ```score```, and returns how many
+/// were freed, for eviction which knows which strings are worth keeping, eg: short common words. Ties are broken
+/// arbitrarily. Nothing is freed if there are ```keep``` or fewer strings. Strings interned from a ```String``` are
+/// scored as well, so ```score``` is given a ```&&str``` which only lives for the call.
+///
+/// # Safety
+/// When this returns more than ```0```, every reference to a freed string previously returned by this crate on this
+/// thread is invalidated, along with those from ```intern_kv``` and ```js_intern_object!```, which are keyed by the
+/// addresses of strings. Like ```clear_string_cache_if_over```, this increases ```intern_epoch```.
+#[cfg(feature = "strings")]
+pub unsafe fn evict_strings_by(score: impl Fn(&&str) -> i64, keep: usize) -> usize {
+	let evicted = STRING_CACHE.with(|c| c.retain_top_by(keep, |s| score(&s.as_ref())));
+	if evicted == 0 {
+		return 0;
+	}
+	// The Bloom filter can't forget strings, which only makes get_interned_str do a lookup for them.
+	STRING_COUNT.with(|c| c.set(c.get() - evicted));
+	collections::clear_string_pointers();
+	strings::clear_string_pointers();
+	EPOCH.with(|e| e.set(e.get() + 1));
+	notify_clear();
+	evicted
+}
+
+/// Frees every interned string on this thread and interns each of ```new``` in its place, for hot reloading a table
+/// of strings. Unlike clearing the string cache and then prewarming it, ```intern_epoch``` is only increased once,
+/// and the function set by ```on_intern_clear``` is called after ```new``` is interned, so it sees the new table.
+/// Values of other types are kept.
+///
+/// # Safety
+/// Every reference to an interned string previously returned by this crate on this thread is invalidated, as by
+/// ```clear_string_cache_if_over```, including those to strings which are also in ```new```.
+#[cfg(feature = "strings")]
+pub unsafe fn reload_strings(new: &[&'static str]) {
+	collections::clear_string_pointers();
+	strings::clear_string_pointers();
+	clear_string_cache();
+	for s in new {
+		s.cache_js_intern__();
+	}
+	EPOCH.with(|e| e.set(e.get() + 1));
+	notify_clear();
+}
+
+/// Sets a function to be called each time interned values are freed on this thread, by ```reset_interner```,
+/// ```clear_string_cache_if_over```, ```evict_strings_by``` or ```reload_strings```, or unsets it with ```None```. Code which holds on to interned values, such as
+/// a cache of its own, can use this to drop them. The function is called after the values are freed, so it may
+/// intern values again.
+pub fn on_intern_clear(callback: Option<fn()>) {
+	ON_CLEAR.with(|c| c.set(callback));
+}
+
+fn notify_clear() {
+	if let Some(callback) = ON_CLEAR.with(|c| c.get()) {
+		callback();
+	}
+}
+
+#[cfg(feature = "strings")]
+fn clear_string_cache() {
+	STRING_CACHE.with(|c| c.clear());
+	STRING_COUNT.with(|c| c.set(0));
+	#[cfg(feature = "bloom")]
+	STRING_BLOOM.with(|b| *b.borrow_mut() = [0; BLOOM_WORDS]);
+}
+
+/// Identifies a slice by its address and length, for caches keyed by the slice itself rather than its contents.
+fn slice_key<T>(slice: &'static [T]) -> (usize, usize) {
+	(slice.as_ptr() as usize, slice.len())
+}
+
+/// A reference to an interned ```JsValue```, as returned by the interning functions of this crate.
+/// It derefs to ```JsValue```, so it can be used anywhere a ```&JsValue``` is expected.
+/// Each value is boxed separately, so its address stays the same for the rest of the thread
+/// no matter how many other values are interned after it, unless ```reset_interner``` is called.
+///
+/// The value belongs to the thread which interned it, so a handle can't be sent to another thread,
+/// even where ```JsValue``` itself can be.
+///
+/// Object-valued handles, such as the arrays, sets, maps and objects built by the helpers of this crate,
+/// are shared and mutable from JavaScript. Anything JavaScript writes to one is seen by every user of it.
+#[derive(Copy, Clone, Debug)]
+pub struct InternHandle(&'static JsValue, PhantomData<*const JsValue>);
+
+impl InternHandle {
+	fn from_raw(value: *mut JsValue) -> InternHandle {
+		InternHandle(as_static(value), PhantomData)
+	}
+
+	/// Returns true if ```other``` is the same instance as this value, as opposed to just an equal value.
+	/// Since interned values are de-duplicated, this is a cheap way to compare them.
+	pub fn ptr_eq(self, other: &JsValue) -> bool {
+		ptr::eq(self.0, other)
+	}
+
+	/// Returns the underlying reference, which is valid for the rest of the thread or until ```reset_interner``` is called.
+	pub fn as_js_value(self) -> &'static JsValue {
+		self.0
+	}
+}
+
+impl Deref for InternHandle {
+	type Target = JsValue;
+	fn deref(&self) -> &JsValue {
+		self.0
+	}
+}
+
+impl AsRef<JsValue> for InternHandle {
+	fn as_ref(&self) -> &JsValue {
+		self.0
+	}
+}
+
+impl From<InternHandle> for &'static JsValue {
+	fn from(handle: InternHandle) -> &'static JsValue {
+		handle.0
 	}
 }
 
@@ -132,29 +939,79 @@ impl From<BitwiseFloat> for f64 {
 /// are de-duplicated across the program. So, any time ```js_intern!("string")```
 /// is used in the program, the same instance of the JavaScript string is used.
 ///
+/// Since each value has one instance, interned values can be compared by address. This gives
+/// an identity even where JavaScript equality can't, as ```NaN !== NaN```, yet ```js_intern!(f64::NAN)```
+/// is always the same instance. Floats are compared by their bit patterns, so a NaN with any other
+/// payload is another value, and ```0.0``` and ```-0.0``` are different instances, unless changed with
+/// ```set_canonicalize_nan``` and ```set_collapse_neg_zero```.
+///
 /// # Supported types
 /// * ```&'static str``` Eg: ```js_intern!("str")```
+/// * ```String```, which shares values with ```&'static str``` Eg: ```js_intern!(String::from("str"))```
 /// * ```f64```, ```f32```, ```u8```, ```u16```, ```u32```, ```i8```, ```i16```, ```i32``` Eg: ```js_intern(1.0)```
 /// * ```bool``` Eg: ```js_intern(true)```
+/// * ```Wrapping``` of any of the above integer types Eg: ```js_intern!(Wrapping(5u32))```
+/// * ```Ordering``` as ```-1```, ```0``` or ```1``` Eg: ```js_intern!(Ordering::Less)```
+/// * ```Option<char>``` as a one character string, or ```undefined``` for ```None``` Eg: ```js_intern!(Some('a'))```
 ///
 /// # Warning: This is intended to work for literals only. It may presently work on expressions,
 /// but this is not an intended part of the API and will break in a future release.
+/// The ```strict-literals``` feature of js-intern makes other expressions a compile error.
+/// With the ```track-call-sites``` feature, a use which interns many distinct values in a build with debug assertions
+/// is reported with a warning on the console, see ```on_non_literal_intern```.
+#[cfg(not(feature = "small"))]
 #[macro_export]
 macro_rules! js_intern {
 	($value:expr) => {
 		{
 			use wasm_bindgen::JsValue;
-			use $crate::CacheJsIntern__;
 			thread_local!(
-				static INTERN: *mut JsValue = $value.cache_js_intern__();
+				static INTERN: ::std::cell::Cell<(u64, *mut JsValue)> = ::std::cell::Cell::new((0, ::std::ptr::null_mut()));
 			);
 
+			// The value is looked up again if reset_interner was called since it was cached.
+			let epoch = $crate::intern_epoch();
+			let (cached, mut value) = INTERN.with(|i| i.get());
+			// With the track-call-sites feature, debug builds evaluate the value every time, so that uses which
+			// intern many distinct values can be reported. Otherwise the value is only evaluated when it's cached.
+			if cached != epoch || $crate::TRACK_CALL_SITES__ {
+				let interned = $crate::cache_js_intern__($value);
+				if $crate::TRACK_CALL_SITES__ {
+					$crate::note_call_site__(interned);
+				}
+				if cached != epoch {
+					value = interned;
+					INTERN.with(|i| i.set((epoch, value)));
+				}
+			}
+
 			// A word about the safety here. We are dereferencing a pointer
 			// of type *mut JsValue. At the address of the pointer is a JsValue
 			// instance that is boxed. The JsValue is only freed when the thread
-			// goes out of scope. JsValue does not implement Send, so we know
-			// that the value cannot be used anywhere it's invalid.
-			unsafe { &*INTERN.with(|i| i.clone()) }
+			// goes out of scope, or by reset_interner, which is unsafe and after
+			// which the value is looked up again. JsValue does not implement Send,
+			// so we know that the value cannot be used anywhere it's invalid.
+			unsafe { &*value }
+		}
+	};
+}
+
+/// Stores one copy of each distinct JavaScript primitive.
+/// This is the version of the macro used with the ```small``` feature. Rather than caching the address
+/// of the value at each use of the macro, the value is looked up each time the expression is evaluated,
+/// which trades some speed for less code at each use.
+#[cfg(feature = "small")]
+#[macro_export]
+macro_rules! js_intern {
+	($value:expr) => {
+		{
+			use wasm_bindgen::JsValue;
+			let value = $crate::cache_js_intern__($value);
+			if $crate::TRACK_CALL_SITES__ {
+				$crate::note_call_site__(value);
+			}
+			// See the note on safety in the default version of this macro.
+			unsafe { &*(value as *const JsValue) }
 		}
 	};
 }
\ No newline at end of file
@@ -0,0 +1,285 @@
+use crate::{as_static, CacheJsIntern__, Cacher, InternHandle};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::panic::Location;
+use std::ptr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wasm_bindgen::prelude::*;
+
+thread_local!(
+	static ID_CACHE: Cacher<(TypeId, u64)> = Cacher::new();
+	// Entries point into the float cache, which owns the values.
+	static APPROX_ENTRIES: RefCell<Vec<(f64, *mut JsValue)>> = RefCell::default();
+);
+
+pub(crate) fn clear_caches() {
+	ID_CACHE.with(|c| c.clear());
+	APPROX_ENTRIES.with(|e| e.borrow_mut().clear());
+}
+
+fn millis(duration: Duration) -> f64 {
+	duration.as_secs() as f64 * 1000.0 + f64::from(duration.subsec_nanos()) / 1_000_000.0
+}
+
+/// Interns a ```SystemTime``` as the number of milliseconds since the Unix epoch, which is
+/// the representation used by JavaScript's ```Date```. Times before the epoch are negative.
+/// The value is the same instance as ```js_intern!``` of the equivalent ```f64```.
+pub fn intern_system_time(time: SystemTime) -> InternHandle {
+	let millis = match time.duration_since(UNIX_EPOCH) {
+		Ok(after) => millis(after),
+		Err(before) => -millis(before.duration()),
+	};
+	InternHandle::from_raw(millis.cache_js_intern__())
+}
+
+/// Interns the center of the bin containing ```value```, where bins are ```bin_width``` wide and
+/// one of them starts at ```origin```. All values in the same bin share one ```JsValue```, which keeps
+/// the number of interned values small for streaming data. ```bin_width``` must be positive.
+/// The value is the same instance as ```js_intern!``` of the center.
+#[track_caller]
+pub fn intern_bin(value: f64, bin_width: f64, origin: f64) -> InternHandle {
+	debug_assert!(bin_width > 0.0, "bin_width must be positive, but was {} at {}", bin_width, Location::caller());
+	let center = origin + (((value - origin) / bin_width).floor() + 0.5) * bin_width;
+	InternHandle::from_raw(center.cache_js_intern__())
+}
+
+/// Interns the result of a comparison as ```-1```, ```0``` or ```1```, as expected from a JavaScript comparator function.
+/// These are looked up in a table rather than hashed, and are the same instances as ```js_intern!``` of the equivalent number.
+pub fn intern_ordering(ordering: Ordering) -> InternHandle {
+	InternHandle::from_raw(ordering.cache_js_intern__())
+}
+
+/// Interns whichever of ```allowed``` is nearest to ```value```, so that only values in the allowed set are ever interned.
+/// This bounds the size of the cache for constrained inputs like zoom levels. Ties go to whichever comes first in ```allowed```,
+/// as does a NaN ```value```. The value is the same instance as ```js_intern!``` of the allowed value.
+///
+/// Panics if ```allowed``` is empty.
+#[track_caller]
+pub fn intern_f64_from_set(value: f64, allowed: &[f64]) -> InternHandle {
+	let mut nearest = match allowed.first() {
+		Some(&first) => first,
+		None => panic!("allowed must not be empty, at {}", Location::caller()),
+	};
+	for &candidate in &allowed[1..] {
+		if (candidate - value).abs() < (nearest - value).abs() {
+			nearest = candidate;
+		}
+	}
+	InternHandle::from_raw(nearest.cache_js_intern__())
+}
+
+/// Interns ```num / den``` as a number, so equal ratios such as ```1 / 2``` and ```2 / 4``` share one ```JsValue```.
+/// Dividing by zero gives positive or negative infinity, except for ```0 / 0```, which gives the NaN singleton.
+/// The value is the same instance as ```js_intern!``` of the quotient.
+///
+/// The ratio is reduced before dividing, so that equal ratios of numbers too large to convert to ```f64``` exactly
+/// still round to the same quotient, and zero is always ```0```, never ```-0```, as in ```0 / -5```.
+pub fn intern_ratio(num: i64, den: i64) -> InternHandle {
+	let ratio = match (num, den) {
+		(0, 0) => f64::NAN,
+		(_, 0) if num > 0 => f64::INFINITY,
+		(_, 0) => f64::NEG_INFINITY,
+		_ => {
+			// In i128, since the divisor of i64::MIN by itself doesn't fit in an i64.
+			let divisor = i128::from(gcd(num.unsigned_abs(), den.unsigned_abs()));
+			(i128::from(num) / divisor) as f64 / (i128::from(den) / divisor) as f64 + 0.0
+		}
+	};
+	InternHandle::from_raw(ratio.cache_js_intern__())
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+	while b != 0 {
+		let rest = a % b;
+		a = b;
+		b = rest;
+	}
+	a
+}
+
+#[doc(hidden)]
+/// This is a private function used by js_intern_id! and not meant to be used.
+pub fn intern_id__(id_type: TypeId, id: u64) -> &'static JsValue {
+	as_static(ID_CACHE.with(|c| {
+		c.cache_with((id_type, id), |&(_, id)| JsValue::from_f64(id as f64))
+	}))
+}
+
+/// Interns the ```u64``` inside a newtype id, such as ```struct UserId(u64)```, as a number.
+/// Each newtype has its own cache, so ```js_intern_id!(UserId, UserId(1))``` and ```js_intern_id!(PostId, PostId(1))```
+/// are equal but distinct instances, while equal ids of the same type are the same instance.
+///
+/// Note that JavaScript numbers can only represent integers up to 2^53 exactly, so larger ids are rounded.
+#[macro_export]
+macro_rules! js_intern_id {
+	($id_type:ty, $id:expr) => {
+		{
+			let id: $id_type = $id;
+			$crate::intern_id__(::std::any::TypeId::of::<$id_type>(), id.0)
+		}
+	};
+}
+
+/// Compares two numbers the way this crate does when interning them, rather than by JavaScript equality.
+/// Interned numbers are equal if and only if they are the same instance, which is checked first. Otherwise,
+/// numbers are equal if their bit patterns are, so unlike ```===```:
+/// * ```NaN``` is equal to ```NaN```, though NaNs with different payloads are not.
+/// * ```0.0``` is not equal to ```-0.0```.
+///
+/// Values which are not numbers are only equal if they are the same instance.
+pub fn interned_num_eq(a: &JsValue, b: &JsValue) -> bool {
+	if ptr::eq(a, b) {
+		return true;
+	}
+	match (a.as_f64(), b.as_f64()) {
+		(Some(a), Some(b)) => a.to_bits() == b.to_bits(),
+		_ => false,
+	}
+}
+
+/// The most values that ```intern_f64_approx``` remembers, which bounds the cost of each lookup.
+pub const APPROX_CAPACITY: usize = 256;
+
+/// Interns a value which should be considered equal to any other within ```epsilon``` of it. If a value within
+/// ```epsilon``` was interned by this function before, that value is returned instead, even if it's not the nearest one.
+/// Unlike ```intern_bin```, which values end up shared depends on the order they are seen in.
+///
+/// Each lookup scans every remembered value, so it takes time proportional to their number. This is meant for small
+/// sets of values, and at most ```APPROX_CAPACITY``` are remembered. Once that many are, other values are interned
+/// exactly. NaN is never within ```epsilon``` of anything, so it's always interned exactly as well.
+/// The value is the same instance as ```js_intern!``` of the returned number.
+pub fn intern_f64_approx(value: f64, epsilon: f64) -> InternHandle {
+	APPROX_ENTRIES.with(|e| {
+		let mut entries = e.borrow_mut();
+		if let Some(&(_, interned)) = entries.iter().find(|(entry, _)| (entry - value).abs() <= epsilon) {
+			return InternHandle::from_raw(interned);
+		}
+		let interned = value.cache_js_intern__();
+		if entries.len() < APPROX_CAPACITY && !value.is_nan() {
+			entries.push((value, interned));
+		}
+		InternHandle::from_raw(interned)
+	})
+}
+
+/// Interns ```degrees``` converted to radians, as by ```degrees.to_radians()```. The value is the same instance as
+/// ```js_intern!``` of the radians, so each distinct angle is only converted to JavaScript once. The conversion itself
+/// is a multiplication, which is cheaper than looking it up, so only the JavaScript value is cached.
+pub fn intern_radians_from_degrees(degrees: f64) -> InternHandle {
+	InternHandle::from_raw(degrees.to_radians().cache_js_intern__())
+}
+
+/// Returns the canonical quiet NaN, the same singleton as ```js_intern!(f64::NAN)```. A NaN with some other payload
+/// is a distinct value to ```js_intern!```, so use this wherever a single, deterministic NaN is needed.
+pub fn intern_quiet_nan() -> InternHandle {
+	InternHandle::from_raw(f64::NAN.cache_js_intern__())
+}
+
+/// Interns ```value``` clamped to ```[0.0, 1.0]```, such as a color channel or an alpha, so that out of range values from
+/// noisy data collapse to the boundaries, eg: ```1.5``` is ```1.0``` and ```-0.3``` is ```0.0```. ```-0.0``` is ```0.0``` as well,
+/// while NaN stays NaN. The value is the same instance as ```js_intern!``` of the clamped number.
+pub fn intern_clamp01(value: f64) -> InternHandle {
+	// Adding zero turns -0.0 into 0.0, which would otherwise be interned separately.
+	InternHandle::from_raw((value.clamp(0.0, 1.0) + 0.0).cache_js_intern__())
+}
+
+/// Interns the index of the frame containing the timestamp ```t```, which is ```(t / frame_ms).floor()```, so that
+/// every timestamp in the same frame shares one ```JsValue```. Both arguments are in the same unit, typically
+/// milliseconds as given to a ```requestAnimationFrame``` callback. ```frame_ms``` must be positive.
+/// The value is the same instance as ```js_intern!``` of the index.
+#[track_caller]
+pub fn intern_frame(t: f64, frame_ms: f64) -> InternHandle {
+	debug_assert!(frame_ms > 0.0, "frame_ms must be positive, but was {} at {}", frame_ms, Location::caller());
+	// Adding zero turns -0.0 into 0.0, which would otherwise be interned separately.
+	let frame = (t / frame_ms).floor() + 0.0;
+	InternHandle::from_raw(frame.cache_js_intern__())
+}
+
+/// Interns finite numbers as numbers, and non-finite numbers as the strings ```"NaN"```, ```"Infinity"``` and
+/// ```"-Infinity"```, as some serializers do since JSON can't represent them. Every NaN becomes ```"NaN"```,
+/// whatever its payload. Numbers are the same instances as ```js_intern!```, and strings the same as ```js_intern!``` of the string.
+#[cfg(feature = "strings")]
+pub fn intern_num_json_safe(value: f64) -> InternHandle {
+	let interned = if value.is_finite() {
+		value.cache_js_intern__()
+	} else if value.is_nan() {
+		"NaN".cache_js_intern__()
+	} else if value > 0.0 {
+		"Infinity".cache_js_intern__()
+	} else {
+		"-Infinity".cache_js_intern__()
+	};
+	InternHandle::from_raw(interned)
+}
+
+/// Interns a UTF-16 code unit as a number, for text algorithms which work on code units, such as
+/// the results of ```charCodeAt```. Like every ```u16```, these are looked up in a table rather than hashed,
+/// and are the same instances as ```js_intern!``` of the number.
+pub fn intern_utf16_unit(unit: u16) -> InternHandle {
+	InternHandle::from_raw(unit.cache_js_intern__())
+}
+
+/// Interns ```value``` rounded to a "nice" number for an axis tick, which is 1, 2 or 5 times a power of ten, so that
+/// ticks are the same instances across redraws. The mantissa rounds down to 1 below 1.5, to 2 below 3, to 5 below 7,
+/// and otherwise up to 10, eg: ```0.037``` becomes ```0.05``` and ```730.0``` becomes ```1000.0```. The sign is kept,
+/// and zero and non-finite values are interned as they are. The value is the same instance as ```js_intern!``` of the tick.
+pub fn intern_nice_tick(value: f64) -> InternHandle {
+	InternHandle::from_raw(nice_tick(value).cache_js_intern__())
+}
+
+fn nice_tick(value: f64) -> f64 {
+	let magnitude = value.abs();
+	if magnitude == 0.0 || !magnitude.is_finite() {
+		return value;
+	}
+	let mut exponent = magnitude.log10().floor() as i32;
+	let mut mantissa = magnitude / 10f64.powi(exponent);
+	// log10 can be off by one ulp at exact powers of ten.
+	if mantissa >= 10.0 {
+		mantissa /= 10.0;
+		exponent += 1;
+	} else if mantissa < 1.0 {
+		mantissa *= 10.0;
+		exponent -= 1;
+	}
+	let nice = if mantissa < 1.5 {
+		1.0
+	} else if mantissa < 3.0 {
+		2.0
+	} else if mantissa < 7.0 {
+		5.0
+	} else {
+		10.0
+	};
+	// Dividing by a power of ten rather than multiplying by its inverse gives eg: exactly 0.2 rather than 0.20000000000000004.
+	let tick = if exponent < 0 { nice / 10f64.powi(-exponent) } else { nice * 10f64.powi(exponent) };
+	tick.copysign(value)
+}
+
+/// Interns the lower bound of the logarithmic bucket containing ```value```, which is ```base``` raised to
+/// ```value.log(base).floor()```, so that data spanning many orders of magnitude, such as file sizes, keeps
+/// only a few interned values. For example, with a base of ```10.0``` everything from ```100.0``` up to but
+/// not including ```1000.0``` is ```100.0```. Negative values are bucketed by their magnitude and keep their sign,
+/// and zero and non-finite values are interned as they are. ```base``` must be greater than one.
+/// The value is the same instance as ```js_intern!``` of the lower bound.
+#[track_caller]
+pub fn intern_log_bucket(value: f64, base: f64) -> InternHandle {
+	debug_assert!(base > 1.0, "base must be greater than one, but was {} at {}", base, Location::caller());
+	InternHandle::from_raw(log_bucket(value, base).cache_js_intern__())
+}
+
+fn log_bucket(value: f64, base: f64) -> f64 {
+	let magnitude = value.abs();
+	if magnitude == 0.0 || !magnitude.is_finite() {
+		return value;
+	}
+	let mut exponent = magnitude.log(base).floor() as i32;
+	// The logarithm can be off by one ulp at exact powers of the base.
+	if base.powi(exponent + 1) <= magnitude {
+		exponent += 1;
+	} else if base.powi(exponent) > magnitude {
+		exponent -= 1;
+	}
+	base.powi(exponent).copysign(value)
+}
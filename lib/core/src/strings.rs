@@ -0,0 +1,620 @@
+use crate::{as_static, cache_cow_str, fnv1a, get_interned_str, slice_key, CacheJsIntern__, Cacher, InternHandle};
+use js_sys::{JsString, Symbol, JSON};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+#[cfg(feature = "graphemes")]
+use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "categories")]
+use unicode_general_category::get_general_category;
+#[cfg(feature = "categories")]
+pub use unicode_general_category::GeneralCategory;
+#[cfg(feature = "width")]
+use unicode_width::UnicodeWidthChar;
+use std::ptr;
+use wasm_bindgen::prelude::*;
+
+thread_local!(
+	// Keyed by the prefix and suffix, and points into the string cache.
+	static PREFIXED_TABLE: RefCell<HashMap<(&'static str, &'static str), *mut JsValue>> = RefCell::default();
+	// JSON.parse never returns undefined, so it marks strings which are not JSON.
+	static JSON_CACHE: Cacher<&'static str> = Cacher::new();
+	static CHAR_CASE_CACHE: Cacher<(char, Case)> = Cacher::new();
+	static JOINED_CACHE: Cacher<((usize, usize), &'static str)> = Cacher::new();
+	// Entries point into the string cache, which owns the values. Unused slots are null.
+	static INDEXED_TABLE: RefCell<Vec<*mut JsValue>> = RefCell::default();
+	// Keyed by the address of the original string, and points into the string cache.
+	static LOWERCASE_TABLE: RefCell<HashMap<(usize, usize), *mut JsValue>> = RefCell::default();
+	static URI_COMPONENT_CACHE: Cacher<(usize, usize)> = Cacher::new();
+	static SYMBOL_CACHE: Cacher<&'static str> = Cacher::new();
+	// The strings being converted by intern_str_async, and the tasks waiting for each. This only holds
+	// wakers rather than values, so it isn't cleared with the caches.
+	static ASYNC_CONVERSIONS: RefCell<HashMap<&'static str, Vec<Waker>>> = RefCell::default();
+	#[cfg(feature = "graphemes")]
+	static GRAPHEME_COUNTS: RefCell<HashMap<&'static str, usize>> = RefCell::default();
+	#[cfg(feature = "graphemes")]
+	static REVERSED_CACHE: Cacher<(usize, usize)> = Cacher::new();
+);
+
+pub(crate) fn clear_caches() {
+	JSON_CACHE.with(|c| c.clear());
+	CHAR_CASE_CACHE.with(|c| c.clear());
+	JOINED_CACHE.with(|c| c.clear());
+	clear_string_pointers();
+	URI_COMPONENT_CACHE.with(|c| c.clear());
+	SYMBOL_CACHE.with(|c| c.clear());
+	#[cfg(feature = "graphemes")]
+	GRAPHEME_COUNTS.with(|g| g.borrow_mut().clear());
+	#[cfg(feature = "graphemes")]
+	REVERSED_CACHE.with(|c| c.clear());
+}
+
+/// Empties the caches which point into the string cache, for when only the string cache is freed.
+pub(crate) fn clear_string_pointers() {
+	// The tables only point into the string cache, so they're emptied rather than freed.
+	INDEXED_TABLE.with(|t| t.borrow_mut().clear());
+	LOWERCASE_TABLE.with(|t| t.borrow_mut().clear());
+	PREFIXED_TABLE.with(|t| t.borrow_mut().clear());
+}
+
+/// The number of indices which ```intern_indexed``` accepts in builds with debug assertions.
+pub const MAX_INDEXED: usize = 1 << 20;
+
+/// Interns a string and records it at ```index``` in a dense table, so that it can later be
+/// retrieved with ```get_indexed(index)``` without hashing. This is intended for compiled string
+/// tables where each string already has a small integer id.
+///
+/// The value is the same instance as ```js_intern!(s)```. Interning a different string at an index
+/// that is already in use replaces the entry in the table.
+///
+/// The table has a slot for every index up to the largest one used, so the ids must be dense and bounded,
+/// such as the positions of the strings in a table. In builds with debug assertions, indices from
+/// ```MAX_INDEXED``` up panic, since they would take a slot for every index before them.
+pub fn intern_indexed(index: usize, s: &'static str) -> InternHandle {
+	debug_assert!(index < MAX_INDEXED, "intern_indexed expects small, dense indices, but was given {}", index);
+	let value = s.cache_js_intern__();
+	INDEXED_TABLE.with(|t| {
+		let mut table = t.borrow_mut();
+		if table.len() <= index {
+			table.resize(index + 1, ptr::null_mut());
+		}
+		table[index] = value;
+	});
+	InternHandle::from_raw(value)
+}
+
+/// Retrieves a string previously stored with ```intern_indexed```, or ```None``` if nothing was stored at ```index```.
+pub fn get_indexed(index: usize) -> Option<InternHandle> {
+	INDEXED_TABLE.with(|t| {
+		match t.borrow().get(index) {
+			Some(value) if !value.is_null() => Some(InternHandle::from_raw(*value)),
+			_ => None,
+		}
+	})
+}
+
+/// Interns a string for passing to web APIs which take a ```DOMString```, such as the text of a DOM text node for
+/// ```document.createTextNode``` or ```textContent```. This is the same instance as ```js_intern!(s)```, and is already
+/// a JavaScript string primitive, so it can be passed as is.
+/// Every string this crate interns is a primitive rather than a ```String``` object, so comparing it with
+/// ```===``` is by value, and it can be viewed as a ```JsString``` with ```unchecked_ref```.
+///
+/// Note that web-sys methods which take a ```&str``` copy and re-encode the string on every call,
+/// so to benefit from interning look for a binding which takes a ```&JsValue``` instead, such as
+/// ```js_sys::Reflect::set``` for properties, or declare your own ```extern``` binding with a ```&JsValue``` parameter.
+pub fn intern_domstring(s: &'static str) -> InternHandle {
+	InternHandle::from_raw(s.cache_js_intern__())
+}
+
+/// Converts each variant of an enum to its name as an interned JavaScript string.
+/// This is usually implemented with ```#[derive(JsInternName)]```.
+pub trait JsInternName {
+	/// Returns the name of the variant. This is the same instance as ```js_intern!``` of the name.
+	fn js_intern_name(&self) -> &'static JsValue;
+}
+
+/// Lists the names of every variant of an enum as an interned JavaScript ```Array```, eg: for the options of a ```<select>```.
+/// This is usually implemented with ```#[derive(JsInternVariants)]```.
+pub trait JsInternVariants {
+	/// Returns the names of the variants in declaration order. The array is built once, frozen as
+	/// by ```intern_str_array_frozen```, and each name is the same instance as ```js_intern!``` of the name.
+	fn js_intern_variants() -> &'static JsValue;
+}
+
+/// Interns ```prefix``` followed by ```suffix```, for namespaced keys like ```"user.name"``` and ```"user.email"```.
+/// This is the same instance as ```js_intern!``` of the full key, however it is split. Each pair of a prefix
+/// and suffix is joined only the first time it is seen, after which it is found without building the full key.
+pub fn intern_str_prefixed(prefix: &'static str, suffix: &'static str) -> InternHandle {
+	if let Some(value) = PREFIXED_TABLE.with(|t| t.borrow().get(&(prefix, suffix)).copied()) {
+		return InternHandle::from_raw(value);
+	}
+	let mut key = String::with_capacity(prefix.len() + suffix.len());
+	key.push_str(prefix);
+	key.push_str(suffix);
+	let value = cache_cow_str(Cow::Owned(key));
+	PREFIXED_TABLE.with(|t| t.borrow_mut().insert((prefix, suffix), value));
+	InternHandle::from_raw(value)
+}
+
+/// The result of ```intern_str_or_json```.
+#[derive(Copy, Clone, Debug)]
+pub enum StrOrJson {
+	/// The string was not valid JSON, so this is the string itself, the same instance as ```js_intern!(s)```.
+	Str(InternHandle),
+	/// The string was valid JSON, so this is the parsed value.
+	Json(InternHandle),
+}
+
+/// Interns either the value parsed from ```s``` if it is valid JSON, or otherwise the string itself.
+/// This is useful for config values which may be plain strings or objects.
+///
+/// Anything ```JSON.parse``` accepts counts as JSON, including numbers like ```"42"``` and quoted strings.
+/// The string is parsed only the first time it is seen.
+pub fn intern_str_or_json(s: &'static str) -> StrOrJson {
+	let parsed = JSON_CACHE.with(|c| {
+		c.cache_with(s, |s| JSON::parse(s).unwrap_or(JsValue::UNDEFINED))
+	});
+	if as_static(parsed).is_undefined() {
+		StrOrJson::Str(InternHandle::from_raw(s.cache_js_intern__()))
+	} else {
+		StrOrJson::Json(InternHandle::from_raw(parsed))
+	}
+}
+
+/// Interns bytes which might not be valid UTF-8 as a string, replacing invalid sequences with
+/// ```U+FFFD REPLACEMENT CHARACTER``` as ```String::from_utf8_lossy``` does. The replacement is deterministic,
+/// so equal bytes always dedupe, and the result is the same instance as ```js_intern!``` of the lossy string.
+/// When a replacement is needed the string is kept by the cache, but only the first time it is seen.
+pub fn intern_str_lossy(bytes: &'static [u8]) -> InternHandle {
+	InternHandle::from_raw(cache_cow_str(String::from_utf8_lossy(bytes)))
+}
+
+#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+enum Case {
+	Upper,
+	Lower,
+}
+
+fn intern_char_case(c: char, case: Case) -> InternHandle {
+	InternHandle::from_raw(CHAR_CASE_CACHE.with(|cache| {
+		cache.cache_with((c, case), |_| {
+			let converted: String = match case {
+				Case::Upper => c.to_uppercase().collect(),
+				Case::Lower => c.to_lowercase().collect(),
+			};
+			JsValue::from_str(&converted)
+		})
+	}))
+}
+
+/// Interns the uppercase form of a char as a string. This may be more than one char, eg: ```'ß'``` becomes ```"SS"```.
+/// The conversion only happens the first time each char is seen.
+pub fn intern_char_upper(c: char) -> InternHandle {
+	intern_char_case(c, Case::Upper)
+}
+
+/// Interns the lowercase form of a char as a string. This may be more than one char, eg: ```'İ'``` becomes ```"i\u{307}"```.
+/// The conversion only happens the first time each char is seen.
+pub fn intern_char_lower(c: char) -> InternHandle {
+	intern_char_case(c, Case::Lower)
+}
+
+/// Interns a char escaped for HTML, as the entity of one of ```&```, ```<```, ```>```, ```"``` and ```'```, such as ```"&amp;"```,
+/// and as a one character string otherwise. The value is the same instance as ```js_intern!``` of the entity,
+/// or of ```Some(c)```. The char is matched each time, since that's cheaper than a lookup.
+pub fn intern_html_entity(c: char) -> InternHandle {
+	let entity = match c {
+		'&' => "&amp;",
+		'<' => "&lt;",
+		'>' => "&gt;",
+		'"' => "&quot;",
+		'\'' => "&#39;",
+		_ => return InternHandle::from_raw(Some(c).cache_js_intern__()),
+	};
+	InternHandle::from_raw(entity.cache_js_intern__())
+}
+
+/// Splits ```s``` on any of the chars in ```seps``` and interns each token. Since each token is a ```'static```
+/// sub-slice of ```s```, nothing is copied on the Rust side, and each token is the same instance as
+/// ```js_intern!``` of it. Empty tokens, such as between two separators in a row, are skipped.
+pub fn intern_tokens(s: &'static str, seps: &[char]) -> Vec<InternHandle> {
+	s.split(seps)
+		.filter(|token| !token.is_empty())
+		.map(|token| InternHandle::from_raw(token.cache_js_intern__()))
+		.collect()
+}
+
+/// Splits a ```class``` attribute such as ```"btn btn-primary"``` on ASCII whitespace, as HTML does, and interns each
+/// class name. Like ```intern_tokens```, each name is a sub-slice of ```s``` and the same instance as ```js_intern!``` of it,
+/// so names which recur across elements are shared.
+pub fn intern_classes(s: &'static str) -> Vec<InternHandle> {
+	s.split_ascii_whitespace()
+		.map(|class| InternHandle::from_raw(class.cache_js_intern__()))
+		.collect()
+}
+
+/// Interns the code point ```cp``` as a one character string, or returns ```None``` if it is not a Unicode
+/// scalar value, such as a surrogate or anything above ```0x10FFFF```. This is the same instance as ```js_intern!```
+/// of the string, and of ```Some``` of the char.
+pub fn intern_codepoint_str(cp: u32) -> Option<InternHandle> {
+	std::char::from_u32(cp).map(|c| InternHandle::from_raw(Some(c).cache_js_intern__()))
+}
+
+/// Interns ```items``` joined with ```sep``` between each of them, as by ```items.join(sep)```, for rendering fixed lists.
+/// The result is keyed by the address of the slice rather than its contents, so a ```static``` or ```const``` table
+/// is only joined once for each separator.
+pub fn intern_joined(items: &'static [&'static str], sep: &'static str) -> InternHandle {
+	InternHandle::from_raw(JOINED_CACHE.with(|c| {
+		c.cache_with((slice_key(items), sep), |_| JsValue::from_str(&items.join(sep)))
+	}))
+}
+
+/// Interns a string which may or may not be ```'static```. This is the same instance as ```js_intern!``` of the string.
+/// Owned strings are kept by the cache the first time their contents are seen, and dropped otherwise.
+pub fn intern_cow_str(s: Cow<'static, str>) -> InternHandle {
+	InternHandle::from_raw(cache_cow_str(s))
+}
+
+/// Interns a ```Cow<'static, str>```, as by ```intern_cow_str```. Unlike ```js_intern!```, the value is looked up
+/// each time the expression is evaluated, so this may be used with strings which are only known at runtime.
+#[macro_export]
+macro_rules! js_intern_cow {
+	($cow:expr) => {
+		$crate::intern_cow_str($cow).as_js_value()
+	};
+}
+
+/// Interns a string along with a 64 bit hash of it, for keying side tables on the Rust side by the same strings.
+/// The hash is FNV-1a, so it is the same for a given string in every program and every release of this crate.
+/// It is computed each time, which is cheaper than looking it up. The value is the same instance as ```js_intern!(s)```.
+pub fn intern_str_hashed(s: &'static str) -> (InternHandle, u64) {
+	(InternHandle::from_raw(s.cache_js_intern__()), fnv1a(s))
+}
+
+/// Interns a string along with the number of extended grapheme clusters in it, which is what a user would
+/// think of as the number of characters, eg: for moving a cursor. The count is only computed the first time
+/// each string is seen. The value is the same instance as ```js_intern!(s)```.
+///
+/// This requires the ```graphemes``` feature.
+#[cfg(feature = "graphemes")]
+pub fn intern_str_with_graphemes(s: &'static str) -> (InternHandle, usize) {
+	let count = GRAPHEME_COUNTS.with(|g| {
+		*g.borrow_mut().entry(s).or_insert_with(|| s.graphemes(true).count())
+	});
+	(InternHandle::from_raw(s.cache_js_intern__()), count)
+}
+
+/// Interns a character as a one character string along with its Unicode general category, eg: for syntax highlighting.
+/// The category is a table lookup, so it isn't cached. The value is the same instance as ```js_intern!(Some(c))```.
+///
+/// This requires the ```categories``` feature.
+#[cfg(feature = "categories")]
+pub fn intern_char_with_category(c: char) -> (InternHandle, GeneralCategory) {
+	(InternHandle::from_raw(Some(c).cache_js_intern__()), get_general_category(c))
+}
+
+/// Interns a character as a one character string along with the number of columns it takes in a monospace layout,
+/// eg: for a terminal. Wide East Asian characters such as ```'中'``` take 2, most others 1, and control characters and
+/// combining marks, which don't take a column of their own, 0. Characters of ambiguous width take 1, as outside of
+/// East Asian contexts. The width is a table lookup, so it isn't cached. The value is the same instance as ```js_intern!(Some(c))```.
+///
+/// This requires the ```width``` feature.
+#[cfg(feature = "width")]
+pub fn intern_char_with_width(c: char) -> (InternHandle, u8) {
+	(InternHandle::from_raw(Some(c).cache_js_intern__()), c.width().unwrap_or(0) as u8)
+}
+
+/// Interns a path with its trailing slashes removed, so that equivalent routes such as ```"/a/"``` and ```"/a"``` share one value.
+/// Every trailing slash is removed, so ```"/a//"``` is ```"/a"``` as well, except that a path of only slashes is the root, ```"/"```.
+/// Nothing else is normalized. The value is the same instance as ```js_intern!``` of the normalized path.
+pub fn intern_path(s: &'static str) -> InternHandle {
+	let trimmed = s.trim_end_matches('/');
+	let path = if trimmed.is_empty() && !s.is_empty() { "/" } else { trimmed };
+	InternHandle::from_raw(path.cache_js_intern__())
+}
+
+/// Interns a string as it is and lowercased, as ```(original, lowercased)```, for case-insensitive lookups which still
+/// display the original. Both are the same instances as ```js_intern!``` of the strings, so case variants such as
+/// ```"Home"``` and ```"HOME"``` share the lowercased value. The string is only lowercased the first time it is seen.
+pub fn intern_str_ci_pair(s: &'static str) -> (InternHandle, InternHandle) {
+	let key = slice_key(s.as_bytes());
+	// The table isn't borrowed while interning, which may call the function set by on_string_miss.
+	let lowercased = match LOWERCASE_TABLE.with(|t| t.borrow().get(&key).copied()) {
+		Some(lowercased) => lowercased,
+		None => {
+			let lowercased = cache_cow_str(Cow::Owned(s.to_lowercase()));
+			LOWERCASE_TABLE.with(|t| t.borrow_mut().insert(key, lowercased));
+			lowercased
+		}
+	};
+	(InternHandle::from_raw(s.cache_js_intern__()), InternHandle::from_raw(lowercased))
+}
+
+/// Interns the result of JavaScript's ```encodeURIComponent(s)```, for building URLs from the same path segments
+/// on each navigation. The string is keyed by its address rather than its contents, and only encoded the first time it is seen.
+pub fn intern_uri_component(s: &'static str) -> InternHandle {
+	InternHandle::from_raw(URI_COMPONENT_CACHE.with(|c| {
+		c.cache_with(slice_key(s.as_bytes()), |_| js_sys::encode_uri_component(s).into())
+	}))
+}
+
+/// Interns the symbol in JavaScript's global registry for ```s```, which is ```Symbol.for(s)```, eg: for symbol keyed objects.
+/// JavaScript returns the same symbol for the same key across the whole runtime, including other realms such as iframes,
+/// so this only saves the lookup in the registry. ```Symbol.keyFor``` of the symbol is ```s```. The symbol is keyed by the
+/// contents of ```s```, and only looked up the first time each string is seen.
+pub fn intern_symbol_for(s: &'static str) -> InternHandle {
+	InternHandle::from_raw(SYMBOL_CACHE.with(|c| c.cache_with(s, |&s| Symbol::for_(s).into())))
+}
+
+/// Interns a string as it is and with leading and trailing whitespace removed, as ```(original, trimmed)```, for form
+/// inputs which are displayed trimmed but stored as typed. Both are the same instances as ```js_intern!``` of the strings,
+/// so whitespace variants such as ```" x "``` and ```"x"``` share the trimmed value. Since the trimmed string is a
+/// ```'static``` sub-slice of ```s```, nothing is copied on the Rust side.
+pub fn intern_str_trim_pair(s: &'static str) -> (InternHandle, InternHandle) {
+	(InternHandle::from_raw(s.cache_js_intern__()), InternHandle::from_raw(s.trim().cache_js_intern__()))
+}
+
+/// A step of the pipeline given to ```intern_str_normalized```.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Normalizer {
+	/// Removes leading and trailing whitespace, as by ```str::trim```.
+	Trim,
+	/// Lowercases the string, as by ```str::to_lowercase```.
+	Lowercase,
+	/// Uppercases the string, as by ```str::to_uppercase```.
+	Uppercase,
+	/// Replaces each run of whitespace with a single space, and removes it from both ends, eg: ```" a \t b "``` becomes ```"a b"```.
+	CollapseWhitespace,
+	/// Composes characters with their combining marks, as by JavaScript's ```s.normalize("NFC")```, so that eg: ```"e\u{301}"```
+	/// becomes ```"\u{e9}"```. This converts the string to JavaScript and back, so it is the slowest of the steps.
+	Nfc,
+}
+
+impl Normalizer {
+	fn apply<'a>(self, s: Cow<'a, str>) -> Cow<'a, str> {
+		match self {
+			Normalizer::Trim => match s {
+				Cow::Borrowed(s) => Cow::Borrowed(s.trim()),
+				Cow::Owned(s) if s.trim().len() == s.len() => Cow::Owned(s),
+				Cow::Owned(s) => Cow::Owned(s.trim().to_owned()),
+			},
+			Normalizer::Lowercase => Cow::Owned(s.to_lowercase()),
+			Normalizer::Uppercase => Cow::Owned(s.to_uppercase()),
+			Normalizer::CollapseWhitespace => Cow::Owned(s.split_whitespace().collect::<Vec<_>>().join(" ")),
+			// normalize only throws for an unknown form, which "NFC" is not.
+			Normalizer::Nfc => Cow::Owned(JsString::from(&*s).normalize("NFC").into()),
+		}
+	}
+}
+
+/// Interns ```s``` after applying each step of ```pipeline``` in order, so that variants of a string which normalize to the
+/// same one share a value, eg: with ```[Normalizer::Trim, Normalizer::Lowercase]```, ```" Hello "``` and ```"HELLO"``` are both
+/// ```"hello"```. The string is normalized each time, and only copied if the result was not already interned, so ```s```
+/// doesn't need to be ```'static```. The value is the same instance as ```js_intern!``` of the normalized string.
+pub fn intern_str_normalized(s: &str, pipeline: &[Normalizer]) -> InternHandle {
+	let normalized = pipeline.iter().fold(Cow::Borrowed(s), |s, normalizer| normalizer.apply(s));
+	match get_interned_str(&normalized) {
+		Some(value) => value,
+		None => InternHandle::from_raw(normalized.into_owned().cache_js_intern__()),
+	}
+}
+
+/// Interns a spreadsheet-like cell, as a number if ```s``` is numeric and as a string otherwise. ```s``` is numeric if
+/// ```s.parse::<f64>()``` succeeds with a finite number, so ```"42"```, ```"-4.2e1"``` and ```"+.5"``` are numbers, while
+/// ```""```, ```" 42"``` with whitespace, ```"0x2a"```, ```"NaN"``` and ```"inf"``` stay strings. The value is the same
+/// instance as ```js_intern!``` of the number or the string. The string is parsed each time, since that's cheaper than a lookup.
+#[cfg(feature = "numbers")]
+pub fn intern_cell(s: &'static str) -> InternHandle {
+	match s.parse::<f64>() {
+		Ok(value) if value.is_finite() => InternHandle::from_raw(value.cache_js_intern__()),
+		_ => InternHandle::from_raw(s.cache_js_intern__()),
+	}
+}
+
+/// Interns ```s``` only if it is entirely ASCII, for APIs which require it, and returns ```None``` otherwise.
+/// Strings which are rejected are not cached. The value is the same instance as ```js_intern!(s)```.
+pub fn intern_str_ascii_only(s: &'static str) -> Option<InternHandle> {
+	if s.is_ascii() {
+		Some(InternHandle::from_raw(s.cache_js_intern__()))
+	} else {
+		None
+	}
+}
+
+/// Interns ```s``` only if it is one of ```allowed```, for enum-like domains such as the values of a form field, and returns
+/// ```None``` otherwise, so that arbitrary strings such as user input can't enter the cache. Since the interned string is
+/// the ```'static``` one from ```allowed```, ```s``` doesn't need to be ```'static```. The slice is searched in order,
+/// which is fast for the small sets this is meant for. The value is the same instance as ```js_intern!``` of the string.
+pub fn intern_str_in_set(s: &str, allowed: &'static [&'static str]) -> Option<InternHandle> {
+	allowed.iter().find(|&&item| item == s).map(|item| InternHandle::from_raw(item.cache_js_intern__()))
+}
+
+/// Interns ```s``` with its extended grapheme clusters in reverse order, so that characters made of several
+/// code points, such as emoji with skin tones or letters with combining accents, stay intact. The string is
+/// keyed by its address rather than its contents, and only reversed the first time it is seen.
+///
+/// This requires the ```graphemes``` feature.
+#[cfg(feature = "graphemes")]
+pub fn intern_str_reversed(s: &'static str) -> InternHandle {
+	InternHandle::from_raw(REVERSED_CACHE.with(|c| {
+		c.cache_with(slice_key(s.as_bytes()), |_| {
+			let reversed: String = s.graphemes(true).rev().collect();
+			JsValue::from_str(&reversed)
+		})
+	}))
+}
+
+/// Interns ```s``` from an async task rather than on the synchronous path, for strings which are expensive to convert,
+/// such as large documents. The first future to be polled yields once and then converts the string on its next poll,
+/// while the others wait for it, so concurrent awaiters share one conversion. If the converting future is dropped
+/// first, one of the waiters takes over. The value is the same instance as ```js_intern!(s)```, and futures for a
+/// string which is already interned are ready immediately.
+pub fn intern_str_async(s: &'static str) -> impl Future<Output = InternHandle> {
+	InternStrAsync { s, converting: false }
+}
+
+struct InternStrAsync {
+	s: &'static str,
+	// Whether this future registered the conversion, and so must finish it.
+	converting: bool,
+}
+
+impl InternStrAsync {
+	/// Unregisters the conversion and wakes the waiters, which find the string interned or take over the conversion.
+	fn finish(&mut self) {
+		if std::mem::replace(&mut self.converting, false) {
+			let waiters = ASYNC_CONVERSIONS.with(|c| c.borrow_mut().remove(self.s));
+			for waker in waiters.into_iter().flatten() {
+				waker.wake();
+			}
+		}
+	}
+}
+
+impl Future for InternStrAsync {
+	type Output = InternHandle;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<InternHandle> {
+		if let Some(value) = get_interned_str(self.s) {
+			self.finish();
+			return Poll::Ready(value);
+		}
+		if self.converting {
+			let value = InternHandle::from_raw(self.s.cache_js_intern__());
+			self.finish();
+			return Poll::Ready(value);
+		}
+		let registered = ASYNC_CONVERSIONS.with(|c| match c.borrow_mut().entry(self.s) {
+			Entry::Occupied(mut waiters) => {
+				if !waiters.get().iter().any(|waker| waker.will_wake(cx.waker())) {
+					waiters.get_mut().push(cx.waker().clone());
+				}
+				false
+			}
+			Entry::Vacant(entry) => {
+				entry.insert(Vec::new());
+				true
+			}
+		});
+		if registered {
+			self.converting = true;
+			cx.waker().wake_by_ref();
+		}
+		Poll::Pending
+	}
+}
+
+impl Drop for InternStrAsync {
+	fn drop(&mut self) {
+		self.finish();
+	}
+}
+
+/// Assigns dense ids to strings, starting from 0 in the order they are first added, and keeps both
+/// directions of the mapping, such as for a dispatcher which receives ids but is configured with names.
+/// Each string is interned as by ```js_intern!```, so ```js_of``` is the same instance it would return.
+///
+/// Like each ```InternHandle```, the values are invalidated by ```reset_interner```.
+#[derive(Default)]
+pub struct StringIdTable {
+	ids: HashMap<&'static str, u32>,
+	entries: Vec<(&'static str, InternHandle)>,
+}
+
+impl StringIdTable {
+	/// Creates an empty table.
+	pub fn new() -> StringIdTable {
+		StringIdTable::default()
+	}
+
+	/// Returns the id of the string, adding it to the table if this is the first time it is seen.
+	pub fn intern(&mut self, s: &'static str) -> u32 {
+		if let Some(&id) = self.ids.get(s) {
+			return id;
+		}
+		let id = self.entries.len() as u32;
+		self.entries.push((s, InternHandle::from_raw(s.cache_js_intern__())));
+		self.ids.insert(s, id);
+		id
+	}
+
+	/// Returns the id of the string, if it has been added.
+	pub fn id_of(&self, s: &str) -> Option<u32> {
+		self.ids.get(s).cloned()
+	}
+
+	/// Returns the string with the id, if there is one.
+	pub fn str_of(&self, id: u32) -> Option<&'static str> {
+		self.entries.get(id as usize).map(|&(s, _)| s)
+	}
+
+	/// Returns the interned value of the string with the id, if there is one.
+	pub fn js_of(&self, id: u32) -> Option<InternHandle> {
+		self.entries.get(id as usize).map(|&(_, handle)| handle)
+	}
+
+	/// Returns the number of strings in the table, which is also the next id to be assigned.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Returns true if no strings have been added.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+}
+
+/// Interns each of ```keys``` up front, and returns how many there were, so that interning a key after it is found by a
+/// lookup is a cache hit. This takes the keys of a ```static``` ```phf::Map``` or ```phf::Set``` as they are, eg:
+/// ```prewarm_phf_keys(KEYWORDS.keys())```, or a slice such as ```&["if", "else"]```, with no dependency on ```phf```.
+pub fn prewarm_phf_keys(keys: impl IntoIterator<Item = &'static &'static str>) -> usize {
+	let mut count = 0;
+	for key in keys {
+		key.cache_js_intern__();
+		count += 1;
+	}
+	count
+}
+
+#[doc(hidden)]
+/// This is a private type used by register_prewarm! and not meant to be used.
+#[cfg(feature = "prewarm")]
+pub struct Prewarm__(pub &'static str);
+
+#[cfg(feature = "prewarm")]
+inventory::collect!(Prewarm__);
+
+#[doc(hidden)]
+#[cfg(feature = "prewarm")]
+pub use inventory;
+
+/// Registers a string literal to be interned by ```prewarm_registered```, so that a library can ask for its strings to be
+/// interned up front without the program listing them. This is used at item scope, eg: ```register_prewarm!("click");```
+/// next to the code which interns the string.
+///
+/// This requires the ```prewarm``` feature.
+#[cfg(feature = "prewarm")]
+#[macro_export]
+macro_rules! register_prewarm {
+	($s:literal) => {
+		$crate::inventory::submit! { $crate::Prewarm__($s) }
+	};
+}
+
+/// Interns every string registered with ```register_prewarm!``` anywhere in the program, and returns how many were registered.
+/// This is meant to be called once from the program's ```#[wasm_bindgen(start)]``` function, since a library can't define one.
+///
+/// This requires the ```prewarm``` feature.
+#[cfg(feature = "prewarm")]
+pub fn prewarm_registered() -> usize {
+	let mut count = 0;
+	for prewarm in inventory::iter::<Prewarm__> {
+		prewarm.0.cache_js_intern__();
+		count += 1;
+	}
+	count
+}
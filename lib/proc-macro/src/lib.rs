@@ -1,8 +1,18 @@
 extern crate proc_macro;
 use proc_macro::*;
-use syn::{parse_macro_input, Expr};
+use syn::{parse_macro_input, Data, DeriveInput, Expr, FieldValue, LitStr, Member, Token, UnOp};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
 use quote::{quote};
+use proc_macro2::Literal;
 
+/// The paths of macros which expand to a string constant, so their uses can be interned by try_js_intern.
+/// Only full paths are recognized, since a macro used by its name alone may be another macro of the same name.
+const CONST_STR_MACROS: &[&str] = &[
+	"std::concat", "std::stringify", "std::env", "std::include_str",
+	"core::concat", "core::stringify", "core::env", "core::include_str",
+	"const_format::concatcp", "const_format::formatcp",
+];
 
 /// Attempts to determine if an expression evaluates to a const. This is in general impossible knowing only the AST without the context.
 /// By way of example, we cannot know if a function call to an ident refers to a function which is a const fn. So, we prefer false negatives
@@ -10,29 +20,222 @@ use quote::{quote};
 // TODO: There are a great many more cases that this can work that should be added.
 fn is_conservatively_const(expr: &Expr) -> bool {
 	match expr {
+		// This includes raw string literals, which quote re-emits verbatim so their contents are never re-escaped.
 		Expr::Lit(lit) => {
 			lit.attrs.is_empty()
 		}
 		Expr::Group(group) => {
 			is_conservatively_const(&group.expr)
 		}
+		// Macros which are known to expand to a &'static str constant, from std and the const_format crate.
+		Expr::Macro(mac) => {
+			let path = mac.mac.path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::");
+			mac.attrs.is_empty() && CONST_STR_MACROS.contains(&path.as_str())
+		}
 		_ => false,
 	}
 }
 
+/// The input of try_js_intern, which may start with ```const``` to opt in to interning an expression that can't be recognized as constant.
+struct TryInput {
+	opt_in: bool,
+	expr: Expr,
+}
+
+impl Parse for TryInput {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let opt_in = input.parse::<Option<Token![const]>>()?.is_some();
+		let expr = input.parse()?;
+		Ok(TryInput { opt_in, expr })
+	}
+}
+
 /// Does a very conservative check to see if an expression is a candidate for interning.
 /// If the expression is a candidate to be interned this will return `js_intern!(...)`
 /// Otherwise, the original expression is returned.
+/// Besides literals, this interns string macros by their full paths, such as ```std::concat!``` and ```const_format::concatcp!```.
+/// Macros used by their name alone, such as ```concat!```, are passed through, since they may be other macros of the same name.
+/// Other constant strings, such as calls to a ```const fn``` or ```const``` items, can be interned by writing
+/// ```const``` in front of them, as in ```try_js_intern!(const greeting())```. The expression is evaluated as a
+/// ```const``` item of type ```&str```, so anything which can't be computed at compile time is a compile error.
 #[proc_macro]
 pub fn try_js_intern(input: TokenStream) -> TokenStream {
 	let orig = input.clone();
+	let TryInput { opt_in, expr } = parse_macro_input!(input as TryInput);
+	if !opt_in && !is_conservatively_const(&expr) {
+		orig
+	} else if opt_in || matches!(expr, Expr::Macro(_)) {
+		// The const item guarantees that the expression is evaluated at compile time.
+		quote!(js_intern::js_intern__!({
+			const VALUE: &str = #expr;
+			VALUE
+		})).into()
+	} else {
+		quote!(js_intern::js_intern__!(#expr)).into()
+	}
+}
+
+fn is_literal(expr: &Expr) -> bool {
+	match expr {
+		Expr::Lit(lit) => lit.attrs.is_empty(),
+		Expr::Group(group) => is_literal(&group.expr),
+		// Negative numbers are parsed as a negation of the literal.
+		Expr::Unary(unary) => match (&unary.op, &*unary.expr) {
+			(UnOp::Neg(_), Expr::Lit(lit)) => lit.attrs.is_empty(),
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/// The same as ```js_intern!```, but only accepts literals such as ```"str"```, ```1.0``` or ```true```.
+/// Anything else is a compile error, rather than working for now and breaking in a future release.
+#[proc_macro]
+pub fn js_intern_strict(input: TokenStream) -> TokenStream {
+	intern_literal(input, "js_intern_strict! only accepts literals, use try_js_intern! for other expressions")
+}
+
+#[doc(hidden)]
+/// This is a private macro used as js_intern! with the strict-literals feature, and not meant to be used.
+#[proc_macro]
+pub fn js_intern_literal__(input: TokenStream) -> TokenStream {
+	intern_literal(input, "js_intern! only accepts literals with the strict-literals feature, use try_js_intern! for other expressions")
+}
+
+fn intern_literal(input: TokenStream, message: &str) -> TokenStream {
 	let expr = parse_macro_input!(input as Expr);
-	if is_conservatively_const(&expr) {
-		quote!({
-			use js_intern::js_intern;
-			js_intern!(#expr)
-		}).into()
+	if is_literal(&expr) {
+		quote!(js_intern::js_intern__!(#expr)).into()
 	} else {
-		orig
+		syn::Error::new_spanned(&expr, message).to_compile_error().into()
+	}
+}
+
+/// Interns a JavaScript object with the given fields, each of which is interned as by ```js_intern!```.
+/// For example, ```js_intern_object!{ name: "x", count: 3 }``` is ```{ name: "x", count: 3 }```.
+/// Objects with the same fields in the same order are the same instance.
+///
+/// Note that JavaScript can still modify the object, which would be seen by every user of it.
+#[proc_macro]
+pub fn js_intern_object(input: TokenStream) -> TokenStream {
+	let parser = Punctuated::<FieldValue, Token![,]>::parse_terminated;
+	let fields = match parser.parse(input) {
+		Ok(fields) => fields,
+		Err(error) => return error.to_compile_error().into(),
+	};
+	let mut entries = Vec::new();
+	for field in fields.iter() {
+		let name = match &field.member {
+			Member::Named(ident) if field.colon_token.is_some() => ident.to_string(),
+			_ => return syn::Error::new_spanned(field, "expected a field, like name: \"value\"").to_compile_error().into(),
+		};
+		let expr = &field.expr;
+		entries.push(quote!((#name, js_intern::js_intern__!(#expr) as *const wasm_bindgen::JsValue)));
+	}
+	quote!(js_intern::intern_object__(&[#(#entries),*])).into()
+}
+
+/// Decodes standard base64 (RFC 4648, with or without padding).
+fn decode_base64(source: &str) -> Result<Vec<u8>, String> {
+	let encoded = source.trim_end_matches('=');
+	let padding = source.len() - encoded.len();
+	if padding > 2 {
+		return Err(String::from("too much padding"));
+	}
+	// Padding fills out the last group of four characters, so padded input comes in whole groups.
+	if padding > 0 && !source.len().is_multiple_of(4) {
+		return Err(String::from("wrong amount of padding"));
+	}
+	let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+	let mut buffer = 0u32;
+	let mut bits = 0;
+	for c in encoded.chars() {
+		let sextet = match c {
+			'A'..='Z' => c as u32 - 'A' as u32,
+			'a'..='z' => c as u32 - 'a' as u32 + 26,
+			'0'..='9' => c as u32 - '0' as u32 + 52,
+			'+' => 62,
+			'/' => 63,
+			_ => return Err(format!("unexpected character {:?}", c)),
+		};
+		buffer = (buffer << 6) | sextet;
+		bits += 6;
+		if bits >= 8 {
+			bits -= 8;
+			bytes.push((buffer >> bits) as u8);
+			buffer &= (1 << bits) - 1;
+		}
+	}
+	// A single leftover sextet can't hold a byte, and any leftover bits must be zero padding.
+	if bits == 6 || buffer != 0 {
+		return Err(String::from("unexpected length"));
+	}
+	Ok(bytes)
+}
+
+/// Interns the bytes of a base64 string literal as a ```Uint8Array```.
+/// The literal is decoded at compile time, so invalid base64 is a compile error.
+/// Any time the same literal is used in the program, the same instance of the JavaScript array is used.
+#[proc_macro]
+pub fn js_intern_base64(input: TokenStream) -> TokenStream {
+	let source = parse_macro_input!(input as LitStr);
+	match decode_base64(&source.value()) {
+		Ok(bytes) => {
+			let bytes = Literal::byte_string(&bytes);
+			quote!({
+				js_intern::intern_base64__(#source, #bytes)
+			}).into()
+		}
+		Err(message) => {
+			syn::Error::new(source.span(), format!("invalid base64: {}", message)).to_compile_error().into()
+		}
 	}
-}
\ No newline at end of file
+}
+
+
+/// Implements ```JsInternName``` for an enum, so that each variant can be converted to its name as an interned JavaScript string.
+#[proc_macro_derive(JsInternName)]
+pub fn derive_js_intern_name(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let data = match &input.data {
+		Data::Enum(data) => data,
+		_ => return syn::Error::new_spanned(&input.ident, "JsInternName can only be derived for enums").to_compile_error().into(),
+	};
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let arms = data.variants.iter().map(|variant| {
+		let ident = &variant.ident;
+		let label = ident.to_string();
+		quote!(#name::#ident { .. } => js_intern::js_intern__!(#label))
+	});
+	quote!(
+		impl #impl_generics js_intern::JsInternName for #name #ty_generics #where_clause {
+			fn js_intern_name(&self) -> &'static wasm_bindgen::JsValue {
+				match self {
+					#(#arms,)*
+				}
+			}
+		}
+	).into()
+}
+
+/// Implements ```JsInternVariants``` for an enum, so that the names of all of its variants can be listed as an interned JavaScript array.
+#[proc_macro_derive(JsInternVariants)]
+pub fn derive_js_intern_variants(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let data = match &input.data {
+		Data::Enum(data) => data,
+		_ => return syn::Error::new_spanned(&input.ident, "JsInternVariants can only be derived for enums").to_compile_error().into(),
+	};
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let labels = data.variants.iter().map(|variant| variant.ident.to_string());
+	quote!(
+		impl #impl_generics js_intern::JsInternVariants for #name #ty_generics #where_clause {
+			fn js_intern_variants() -> &'static wasm_bindgen::JsValue {
+				static VARIANTS: &[&str] = &[#(#labels),*];
+				js_intern::intern_str_array_frozen(VARIANTS).as_js_value()
+			}
+		}
+	).into()
+}
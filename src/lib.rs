@@ -3,24 +3,212 @@
 //! and a one-time translation from the utf-8 Rust string to the utf-16 JavaScript string the first time the expression is evaluated.
 //! Furthermore, strings are de-duplicated across the program.
 //! So, any time ```js_intern!(1.0)``` is used in the program, the same instance of the JavaScript number is used.
+//! This even holds for ```f64::NAN```, which can be compared by address where ```NaN !== NaN``` in JavaScript.
 //!
 //! # Supported types
 //! * ```&'static str``` Eg: ```js_intern!("str")```
+//! * ```String```, which shares values with ```&'static str``` Eg: ```js_intern!(String::from("str"))```
 //! * ```f64```, ```f32```, ```u8```, ```u16```, ```u32```, ```i8```, ```i16```, ```i32``` Eg: ```js_intern(1.0)```
 //! * ```bool``` Eg: ```js_intern(true)```
+//! * ```Wrapping``` of any of the above integer types Eg: ```js_intern!(Wrapping(5u32))```
+//! * ```Ordering``` as ```-1```, ```0``` or ```1``` Eg: ```js_intern!(Ordering::Less)```
+//! * ```Option<char>``` as a one character string, or ```undefined``` for ```None``` Eg: ```js_intern!(Some('a'))```
+//!
+//! # Features
+//! * ```small``` Makes each use of ```js_intern!``` generate less code, at the cost of a hash lookup every time the expression is evaluated rather than only the first time. Bools are also stored as two singletons rather than in a map.
+//! * ```strings```, ```numbers``` and ```bools``` Compile in the caches behind ```js_intern!``` of each type, and the helpers which use them. These are enabled by default, so a program which only interns strings can use ```default-features = false, features = ["strings"]```, after which ```js_intern!``` of a number is a compile error rather than code in the binary. ```Ordering``` and ```Wrapping``` are interned as numbers and ```Option<char>``` as a string, so they need those features too. ```graphemes```, ```categories```, ```width```, ```prewarm``` and ```bloom``` each enable ```strings```.
+//! * ```strict-literals``` Makes ```js_intern!``` a compile error for anything other than a literal, as ```js_intern_strict!``` is, rather than working for now and breaking in a future release.
+//! * ```graphemes``` Adds ```intern_str_with_graphemes```, which also counts the grapheme clusters in a string, and ```intern_str_reversed```. This depends on ```unicode-segmentation```.
+//! * ```categories``` Adds ```intern_char_with_category```, which also looks up the Unicode general category of a character. This depends on ```unicode-general-category```.
+//! * ```width``` Adds ```intern_char_with_width```, which also computes the number of columns a character takes in a monospace layout. This depends on ```unicode-width```.
+//! * ```prewarm``` Adds ```register_prewarm!```, which registers a string literal from anywhere in the program to be interned by ```prewarm_registered```. This depends on ```inventory```.
+//! * ```bloom``` Puts a Bloom filter in front of the string cache, so that ```get_interned_str``` can reject most strings which were never interned without a hash map lookup. This costs 8KiB per thread, and a little time whenever a new string is interned.
+//! * ```leak-forever``` Leaves out freeing the values interned by ```js_intern!``` and the helpers when a thread exits, for programs whose threads live as long as they do, such as the main thread of a browser, where the teardown never runs anyway. Values are still freed by ```reset_interner``` and when an ```Interner``` is dropped. The tradeoff is that a thread which does exit, such as a web worker sharing memory with other threads, leaks everything it interned.
+//! * ```track-call-sites``` Reports uses of ```js_intern!``` which intern many distinct values in builds with debug assertions, since they are likely not used with a literal. See ```on_non_literal_intern```. To see every value, each use evaluates its expression every time rather than only the first time, which costs a hash lookup and some bookkeeping on each evaluation.
 //!
 //! # Related
 //! If you like this, you may like these other crates by Zac Burns (That3Percent)
 //! * [js-object](https://github.com/That3Percent/js-object) A macro for creating JavaScript objects
 //! * [soa-vec](https://github.com/That3Percent/soa-vec) A struct of arrays layout with a Vec of tuple API
 //! * [second-stack](https://github.com/That3Percent/second-stack) A memory allocator for large slices that don't escape the stack.
+#[cfg(not(feature = "strict-literals"))]
 pub use js_intern_core::js_intern;
-pub use js_intern_proc_macro::try_js_intern;
+#[cfg(feature = "strict-literals")]
+pub use js_intern_proc_macro::js_intern_literal__ as js_intern;
+pub use js_intern_core::{
+	intern_debug_assert_interned,
+	InternHandle,
+	Interner, InternerBuilder, InternKey,
+	intern_point,
+	intern_bytes, intern_cow_bytes, intern_opt_bytes,
+	intern_f64_vec_to_typed,
+	intern_f32_slice,
+	intern_str_utf8_bytes,
+	intern_dataview,
+	intern_deltas,
+	associate, get_association,
+	intern_diagnostics_json,
+	on_non_literal_intern,
+	intern_num_str_prec,
+	intern_num_str_sci,
+	intern_value_unit,
+	intern_bytes_hex,
+	intern_money_str,
+	intern_fixed,
+	intern_percent_str,
+	intern_iso_timestamp,
+	reset_interner,
+	on_intern_clear,
+	intern_epoch,
+	reintern_from_js,
+};
+#[cfg(feature = "strings")]
+pub use js_intern_core::{
+	js_intern_cow,
+	intern_string_set, intern_string_map,
+	intern_kv,
+	intern_indexed, get_indexed, MAX_INDEXED,
+	intern_domstring,
+	intern_str_prefixed,
+	intern_str_or_json, StrOrJson,
+	intern_str_lossy,
+	intern_char_upper, intern_char_lower,
+	intern_html_entity,
+	intern_tokens,
+	intern_joined,
+	intern_codepoint_str,
+	intern_cow_str,
+	intern_str_ascii_only,
+	intern_str_in_set,
+	StringIdTable,
+	intern_str_hashed,
+	intern_path,
+	intern_str_ci_pair,
+	intern_str_trim_pair,
+	intern_str_normalized, Normalizer,
+	intern_uri_component,
+	intern_symbol_for,
+	intern_classes,
+	prewarm_phf_keys,
+	intern_str_async,
+	JsInternName, JsInternVariants,
+	intern_str_array_frozen,
+	intern_stats_object,
+	clear_string_cache_if_over,
+	evict_strings_by,
+	reload_strings,
+	on_string_miss,
+	set_string_cache_limit, try_intern_str, CacheFull,
+	get_interned_str,
+};
+#[cfg(feature = "numbers")]
+pub use js_intern_core::{
+	js_intern_id,
+	intern_range,
+	intern_f64_range,
+	intern_utf16_unit,
+	intern_system_time,
+	intern_bin,
+	intern_ordering,
+	intern_f64_from_set,
+	intern_ratio,
+	interned_num_eq,
+	intern_f64_approx, APPROX_CAPACITY,
+	intern_radians_from_degrees,
+	intern_quiet_nan,
+	intern_clamp01,
+	intern_frame,
+	intern_nice_tick,
+	intern_log_bucket,
+	set_canonicalize_nan, set_collapse_neg_zero,
+};
+#[cfg(feature = "bools")]
+pub use js_intern_core::{
+	intern_bool, intern_bool_pair, intern_tristate,
+	intern_bool_jsarray,
+};
+#[cfg(all(feature = "strings", feature = "numbers"))]
+pub use js_intern_core::{
+	intern_cell,
+	intern_num_json_safe,
+	intern_record,
+};
+#[cfg(feature = "graphemes")]
+pub use js_intern_core::{intern_str_with_graphemes, intern_str_reversed};
+#[cfg(feature = "categories")]
+pub use js_intern_core::{intern_char_with_category, GeneralCategory};
+#[cfg(feature = "width")]
+pub use js_intern_core::intern_char_with_width;
+#[cfg(feature = "prewarm")]
+pub use js_intern_core::{register_prewarm, prewarm_registered};
+#[doc(hidden)]
+pub use js_intern_core::intern_base64__;
+#[cfg(feature = "strings")]
+#[doc(hidden)]
+pub use js_intern_core::intern_object__;
+// The proc macros expand to this rather than js_intern!, which may be strict depending on the features.
+#[doc(hidden)]
+pub use js_intern_core::js_intern as js_intern__;
+pub use js_intern_proc_macro::{try_js_intern, js_intern_strict, js_intern_object, js_intern_base64, JsInternName, JsInternVariants};
 
+// The proc macros expand to a path through js_intern, which must resolve from within our own tests too.
 #[cfg(test)]
+extern crate self as js_intern;
+
+// The tests cover every type, so they need each of the default features.
+#[cfg(all(test, feature = "strings", feature = "numbers", feature = "bools"))]
+// Some of the tests predate the associated float constants, such as f64::NAN.
+#[allow(clippy::legacy_numeric_constants)]
 mod tests {
 	use super::*;
+	// Many of the tests intern expressions, which the strict-literals feature doesn't allow.
+	use js_intern_core::js_intern;
+	use wasm_bindgen::{JsCast, JsValue};
 	use wasm_bindgen_test::*;
+	use js_sys::{Array, DataView, Float32Array, Float64Array, JsString, Map, Number, Object, Reflect, Set, Uint8Array};
+	use std::borrow::Cow;
+	use std::cmp::Ordering;
+	use std::num::Wrapping;
+	use std::time::{Duration, UNIX_EPOCH};
+	use std::alloc::{GlobalAlloc, Layout, System};
+	use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+	/// Counts heap allocations, to check that each distinct value is only allocated once.
+	struct CountingAllocator;
+
+	static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+	unsafe impl GlobalAlloc for CountingAllocator {
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+			ALLOCATIONS.fetch_add(1, AtomicOrdering::Relaxed);
+			System.alloc(layout)
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+			#[cfg(not(feature = "small"))]
+			{
+				if ptr as usize == WATCHED.load(AtomicOrdering::Relaxed) {
+					WATCHED_FREED.store(true, AtomicOrdering::Relaxed);
+				}
+			}
+			System.dealloc(ptr, layout)
+		}
+	}
+
+	// An address to be watched for being freed, by leaks_values_on_thread_exit.
+	#[cfg(not(feature = "small"))]
+	static WATCHED: AtomicUsize = AtomicUsize::new(0);
+	#[cfg(not(feature = "small"))]
+	static WATCHED_FREED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+	#[global_allocator]
+	static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+	fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+		let before = ALLOCATIONS.load(AtomicOrdering::Relaxed);
+		f();
+		ALLOCATIONS.load(AtomicOrdering::Relaxed) - before
+	}
 
 	#[wasm_bindgen_test]
 	fn can_convert_f64() {
@@ -34,11 +222,36 @@ mod tests {
 		assert!(js_intern!(std::f64::NAN).as_f64().unwrap().is_nan());
 	}
 
+	#[wasm_bindgen_test]
+	fn deduplicates_small_int() {
+		assert_eq!(js_intern!(200u8).as_f64(), Some(200.0));
+		assert_eq!(js_intern!(200u8) as *const _, js_intern!(200u8) as *const _);
+		assert_eq!(js_intern!(200u8) as *const _, js_intern!(200i16) as *const _);
+		assert_eq!(js_intern!(200u8) as *const _, js_intern!(200.0) as *const _);
+		assert_eq!(js_intern!(-128i8) as *const _, js_intern!(-128i16) as *const _);
+		assert_eq!(js_intern!(i16::MIN).as_f64(), Some(-32768.0));
+		assert_eq!(js_intern!(u16::MAX).as_f64(), Some(65535.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_wrapping() {
+		assert_eq!(js_intern!(Wrapping(5u32)).as_f64(), Some(5.0));
+		assert_eq!(js_intern!(Wrapping(5u32)) as *const _, js_intern!(5u32) as *const _);
+		assert_eq!(js_intern!(Wrapping(-3i8)) as *const _, js_intern!(-3i8) as *const _);
+	}
+
 	#[wasm_bindgen_test]
 	fn deduplicates_f64() {
 		assert_eq!(js_intern!(15.0) as *const _, js_intern!(15.0) as *const _);
 	}
 
+	#[wasm_bindgen_test]
+	fn deduplicates_nan() {
+		assert_eq!(js_intern!(f64::NAN) as *const _, js_intern!(f64::NAN) as *const _);
+		assert_eq!(js_intern!(f64::NAN) as *const _, js_intern!(f32::NAN) as *const _);
+		assert!(js_intern!(f64::NAN).as_f64().unwrap().is_nan());
+	}
+
 	#[wasm_bindgen_test]
 	fn can_convert_str() {
 		assert_eq!(js_intern!("b").as_string(), Some(String::from("b")));
@@ -59,14 +272,2084 @@ mod tests {
 		assert_eq!(js_intern!(true) as *const _, js_intern!(true) as *const _);
 	}
 
-	// TODO: It would be nice to have tests around try_js_intern, but that would require enabling the proc_macro_hygiene feature,
-	// but I'm not sure what effect that would have on crates which would rely only on js_intern and not try_js_intern if they
-	// would also need to upgrade to nightly
-	/*
+	#[wasm_bindgen_test]
+	fn deduplicates_false() {
+		assert_eq!(js_intern!(false).as_bool(), Some(false));
+		assert_eq!(js_intern!(false) as *const _, js_intern!(false) as *const _);
+		assert_ne!(js_intern!(false) as *const _, js_intern!(true) as *const _);
+	}
+
 	#[wasm_bindgen_test]
 	fn try_deduplicates_str_lit() {
 		assert_eq!(try_js_intern!("a") as *const _, js_intern!("a") as *const _);
 	}
-	*/
 
-}
\ No newline at end of file
+	const fn greeting() -> &'static str {
+		"hello"
+	}
+
+	#[wasm_bindgen_test]
+	fn try_interns_opted_in_const() {
+		assert_eq!(try_js_intern!(const greeting()) as *const _, js_intern!("hello") as *const _);
+		assert_eq!(try_js_intern!(const PREFIX) as *const _, js_intern!("app") as *const _);
+	}
+
+	const PREFIX: &str = "app";
+
+	#[wasm_bindgen_test]
+	fn try_interns_const_str_macros() {
+		assert_eq!(try_js_intern!(std::concat!("a", "b")) as *const _, js_intern!("ab") as *const _);
+		assert_eq!(try_js_intern!(::core::stringify!(name)) as *const _, js_intern!("name") as *const _);
+		assert_eq!(try_js_intern!(const_format::concatcp!(PREFIX, ".", 1u8)) as *const _, js_intern!("app.1") as *const _);
+		assert_eq!(try_js_intern!(const_format::formatcp!("{}:{}", PREFIX, "main")).as_string(), Some(String::from("app:main")));
+	}
+
+	#[wasm_bindgen_test]
+	fn try_passes_through_macros_by_name() {
+		let std_concat: &str = try_js_intern!(concat!("a", "b"));
+		assert_eq!(std_concat, "ab");
+		// A macro of the same name as one from std, which isn't constant.
+		macro_rules! concat {
+			($($part:expr),*) => { [$($part),*].concat() };
+		}
+		let owned: String = try_js_intern!(concat!("a", "b"));
+		assert_eq!(owned, "ab");
+	}
+
+	#[wasm_bindgen_test]
+	fn try_passes_through_other_calls() {
+		let not_const: String = try_js_intern!(String::from("a"));
+		assert_eq!(not_const, "a");
+		let not_opted_in: &str = try_js_intern!(greeting());
+		assert_eq!(not_opted_in, "hello");
+	}
+
+	#[wasm_bindgen_test]
+	fn preserves_raw_str() {
+		assert_eq!(js_intern!(r"C:\path\to").as_string(), Some(String::from("C:\\path\\to")));
+		assert_eq!(try_js_intern!(r#"{"a":1}"#).as_string(), Some(String::from("{\"a\":1}")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_raw_str_with_escaped_str() {
+		assert_eq!(js_intern!(r"a\b") as *const _, js_intern!("a\\b") as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_point() {
+		assert!(intern_point(1.0, 2.0).ptr_eq(&intern_point(1.0, 2.0)));
+		assert!(!intern_point(1.0, 2.0).ptr_eq(&intern_point(2.0, 1.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_point() {
+		let point: &Float64Array = intern_point(3.0, 4.0).as_js_value().dyn_ref().unwrap();
+		assert_eq!(point.to_vec(), vec![3.0, 4.0]);
+	}
+
+	#[wasm_bindgen_test]
+	fn round_trips_indexed() {
+		intern_indexed(3, "three");
+		intern_indexed(0, "zero");
+		assert_eq!(get_indexed(3).unwrap().as_string(), Some(String::from("three")));
+		assert!(get_indexed(0).unwrap().ptr_eq(js_intern!("zero")));
+		assert!(get_indexed(1).is_none());
+		assert!(get_indexed(100).is_none());
+	}
+
+	// This panics before calling into JavaScript, so it runs as a native test.
+	#[cfg(debug_assertions)]
+	#[test]
+	#[should_panic(expected = "small, dense indices")]
+	fn rejects_sparse_indices() {
+		intern_indexed(MAX_INDEXED, "far away");
+	}
+
+	#[wasm_bindgen_test]
+	fn can_convert_system_time() {
+		assert_eq!(intern_system_time(UNIX_EPOCH + Duration::from_millis(1500)).as_f64(), Some(1500.0));
+		// Some targets, like wasm32-unknown-unknown, can't represent times before the epoch.
+		if let Some(before) = UNIX_EPOCH.checked_sub(Duration::from_millis(250)) {
+			assert_eq!(intern_system_time(before).as_f64(), Some(-250.0));
+		}
+		assert_eq!(intern_system_time(UNIX_EPOCH).as_f64(), Some(0.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_system_time() {
+		let time = UNIX_EPOCH + Duration::from_millis(1500);
+		assert!(intern_system_time(time).ptr_eq(&intern_system_time(time)));
+		assert!(intern_system_time(time).ptr_eq(js_intern!(1500.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_pass_domstring_without_converting() {
+		let element = Object::new();
+		Reflect::set(&element, &intern_domstring("id"), js_intern!("main")).unwrap();
+		assert_eq!(Reflect::get(&element, js_intern!("id")).unwrap().as_string(), Some(String::from("main")));
+		assert!(intern_domstring("id").ptr_eq(js_intern!("id")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_decode_base64() {
+		let bytes: &Uint8Array = js_intern_base64!("SGVsbG8=").dyn_ref().unwrap();
+		assert_eq!(bytes.to_vec(), b"Hello".to_vec());
+		let bytes: &Uint8Array = js_intern_base64!("").dyn_ref().unwrap();
+		assert_eq!(bytes.length(), 0);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_base64() {
+		assert_eq!(js_intern_base64!("SGk") as *const _, js_intern_base64!("SGk") as *const _);
+		assert_ne!(js_intern_base64!("SGk") as *const _, js_intern_base64!("SGVsbG8=") as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_use_intern_handle() {
+		let handle = intern_domstring("handle");
+		assert_eq!(handle.as_string(), Some(String::from("handle")));
+		assert!(handle.ptr_eq(js_intern!("handle")));
+		assert!(!handle.ptr_eq(&JsValue::from_str("handle")));
+		let value: &'static JsValue = handle.into();
+		assert_eq!(value as *const _, js_intern!("handle") as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_bytes() {
+		let bytes: &Uint8Array = intern_bytes(&[1, 2, 3]).as_js_value().dyn_ref().unwrap();
+		assert_eq!(bytes.to_vec(), vec![1, 2, 3]);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_cow_bytes() {
+		let borrowed = intern_cow_bytes(Cow::Borrowed(b"cow"));
+		let owned = intern_cow_bytes(Cow::Owned(b"cow".to_vec()));
+		assert!(borrowed.ptr_eq(&owned));
+		assert!(owned.ptr_eq(&intern_cow_bytes(Cow::Owned(b"cow".to_vec()))));
+		assert!(owned.ptr_eq(&intern_bytes(b"cow")));
+		let owned_first = intern_cow_bytes(Cow::Owned(b"owned".to_vec()));
+		assert!(owned_first.ptr_eq(&intern_cow_bytes(Cow::Borrowed(b"owned"))));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_bin() {
+		assert!(intern_bin(1.1, 1.0, 0.0).ptr_eq(&intern_bin(1.9, 1.0, 0.0)));
+		assert!(intern_bin(1.1, 1.0, 0.0).ptr_eq(js_intern!(1.5)));
+		assert!(!intern_bin(1.1, 1.0, 0.0).ptr_eq(&intern_bin(2.1, 1.0, 0.0)));
+		assert_eq!(intern_bin(-0.2, 1.0, 0.0).as_f64(), Some(-0.5));
+		assert_eq!(intern_bin(12.0, 5.0, 2.0).as_f64(), Some(14.5));
+	}
+
+	#[derive(JsInternName, JsInternVariants)]
+	#[allow(dead_code)]
+	enum Shape {
+		Circle,
+		Square(f64),
+		Rectangle { width: f64, height: f64 },
+	}
+
+	#[wasm_bindgen_test]
+	fn can_name_variants() {
+		assert_eq!(Shape::Circle.js_intern_name().as_string(), Some(String::from("Circle")));
+		assert_eq!(Shape::Square(1.0).js_intern_name().as_string(), Some(String::from("Square")));
+		let rectangle = Shape::Rectangle { width: 1.0, height: 2.0 };
+		assert_eq!(rectangle.js_intern_name().as_string(), Some(String::from("Rectangle")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_variant_names() {
+		assert_eq!(Shape::Square(1.0).js_intern_name() as *const _, Shape::Square(2.0).js_intern_name() as *const _);
+		assert_eq!(Shape::Circle.js_intern_name() as *const _, js_intern!("Circle") as *const _);
+	}
+
+	#[derive(JsInternVariants)]
+	#[allow(dead_code)]
+	enum Size {
+		Small,
+		Medium(u32),
+		Large { extra: bool },
+	}
+
+	#[wasm_bindgen_test]
+	fn can_list_variants() {
+		let variants: &Array = Size::js_intern_variants().dyn_ref().unwrap();
+		let names: Vec<String> = variants.iter().map(|name| name.as_string().unwrap()).collect();
+		assert_eq!(names, vec!["Small", "Medium", "Large"]);
+		assert!(Object::is_frozen(variants));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_variant_lists() {
+		assert_eq!(Size::js_intern_variants() as *const _, Size::js_intern_variants() as *const _);
+		assert!(Shape::js_intern_variants() != Size::js_intern_variants());
+	}
+
+	#[derive(JsInternVariants)]
+	#[allow(dead_code)]
+	enum Slot<T> {
+		Empty,
+		Full(T),
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_variant_lists_of_generic_enums() {
+		assert_eq!(Slot::<u8>::js_intern_variants() as *const _, Slot::<String>::js_intern_variants() as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_prefixed_str() {
+		let name = intern_str_prefixed("user.", "name");
+		assert_eq!(name.as_string(), Some(String::from("user.name")));
+		assert!(name.ptr_eq(&intern_str_prefixed("user.", "name")));
+		let email = intern_str_prefixed("user.", "email");
+		assert_eq!(email.as_string(), Some(String::from("user.email")));
+		assert!(!email.ptr_eq(&name));
+	}
+
+	#[wasm_bindgen_test]
+	fn prefixed_is_same_as_full_key() {
+		let name = intern_str_prefixed("user.", "name");
+		assert!(name.ptr_eq(js_intern!("user.name")));
+		assert!(name.ptr_eq(&intern_str_prefixed("user", ".name")));
+	}
+
+	static COLORS: [&str; 4] = ["red", "green", "blue", "red"];
+
+	#[wasm_bindgen_test]
+	fn can_check_string_set() {
+		let set: &Set = intern_string_set(&COLORS).as_js_value().dyn_ref().unwrap();
+		assert!(set.has(js_intern!("green")));
+		assert!(!set.has(js_intern!("purple")));
+		assert_eq!(set.size(), 3);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_string_set() {
+		assert!(intern_string_set(&COLORS).ptr_eq(&intern_string_set(&COLORS)));
+	}
+
+	static CONFIG: [(&str, &str); 2] = [("theme", "dark"), ("lang", "en")];
+
+	#[wasm_bindgen_test]
+	fn can_get_from_string_map() {
+		let map: &Map = intern_string_map(&CONFIG).as_js_value().dyn_ref().unwrap();
+		assert_eq!(map.get(js_intern!("theme")).as_string(), Some(String::from("dark")));
+		assert_eq!(map.get(js_intern!("lang")), *js_intern!("en"));
+		assert!(map.get(js_intern!("missing")).is_undefined());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_string_map() {
+		assert!(intern_string_map(&CONFIG).ptr_eq(&intern_string_map(&CONFIG)));
+	}
+
+	#[wasm_bindgen_test]
+	fn str_address_is_stable_under_churn() {
+		let first = intern_domstring("stable");
+		for i in 0..100 {
+			intern_domstring(Box::leak(format!("churn {}", i).into_boxed_str()));
+		}
+		assert!(first.ptr_eq(&intern_domstring("stable")));
+		assert_eq!(first.as_string(), Some(String::from("stable")));
+	}
+
+	#[wasm_bindgen_test]
+	fn debug_assert_interned_passes() {
+		intern_debug_assert_interned!(js_intern!("checked"));
+		intern_debug_assert_interned!(js_intern!(f64::NAN));
+		intern_debug_assert_interned!(js_intern!(false));
+		intern_debug_assert_interned!(&intern_point(5.0, 6.0));
+	}
+
+	#[cfg(debug_assertions)]
+	#[wasm_bindgen_test]
+	#[should_panic]
+	fn debug_assert_interned_fails() {
+		intern_debug_assert_interned!(&JsValue::from_str("checked"));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_format_num_str_prec() {
+		assert_eq!(intern_num_str_prec(1.5, 2).as_string(), Some(format!("{:.*}", 2, 1.5)));
+		assert_eq!(intern_num_str_prec(2.0 / 3.0, 3).as_string(), Some(String::from("0.667")));
+		assert_eq!(intern_num_str_prec(42.0, 0).as_string(), Some(String::from("42")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_num_str_prec() {
+		assert!(intern_num_str_prec(1.5, 2).ptr_eq(&intern_num_str_prec(1.5, 2)));
+		assert!(!intern_num_str_prec(1.5, 2).ptr_eq(&intern_num_str_prec(1.5, 1)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_format_num_str_sci() {
+		assert_eq!(intern_num_str_sci(1500.0).as_string(), Some(String::from("1.5e3")));
+		assert_eq!(intern_num_str_sci(0.000_012_5).as_string(), Some(String::from("1.25e-5")));
+		assert_eq!(intern_num_str_sci(6.02e23).as_string(), Some(format!("{:e}", 6.02e23)));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_num_str_sci() {
+		assert!(intern_num_str_sci(1500.0).ptr_eq(&intern_num_str_sci(1500.0)));
+		assert!(!intern_num_str_sci(0.0).ptr_eq(&intern_num_str_sci(-0.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_plain_str_or_json() {
+		match intern_str_or_json("plain") {
+			StrOrJson::Str(s) => assert!(s.ptr_eq(js_intern!("plain"))),
+			StrOrJson::Json(_) => panic!("plain is not JSON"),
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_json_object_str_or_json() {
+		let parsed = match intern_str_or_json(r#"{"a":1}"#) {
+			StrOrJson::Json(parsed) => parsed,
+			StrOrJson::Str(_) => panic!("expected JSON"),
+		};
+		assert_eq!(Reflect::get(&parsed, js_intern!("a")).unwrap().as_f64(), Some(1.0));
+		match intern_str_or_json(r#"{"a":1}"#) {
+			StrOrJson::Json(again) => assert!(again.ptr_eq(&parsed)),
+			StrOrJson::Str(_) => panic!("expected JSON"),
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_f64_vec_to_typed() {
+		let values: Vec<f64> = (0..4).map(f64::from).collect();
+		let array = intern_f64_vec_to_typed(&values);
+		assert!(array.ptr_eq(&intern_f64_vec_to_typed(&values.clone())));
+		assert!(!array.ptr_eq(&intern_f64_vec_to_typed(&values[1..])));
+		let typed: &Float64Array = array.as_js_value().dyn_ref().unwrap();
+		assert_eq!(typed.to_vec(), values);
+	}
+
+	#[wasm_bindgen_test]
+	fn interns_f64_vec_to_typed_without_allocating() {
+		let values: Vec<f64> = (0..64).map(f64::from).collect();
+		let array = intern_f64_vec_to_typed(&values);
+		// Each lookup reuses the buffer, rather than allocating a key the size of the array.
+		for _ in 0..100 {
+			assert_eq!(count_allocations(|| assert!(array.ptr_eq(&intern_f64_vec_to_typed(&values)))), 0);
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_ordering() {
+		assert!(intern_ordering(Ordering::Less).ptr_eq(js_intern!(-1.0)));
+		assert!(intern_ordering(Ordering::Equal).ptr_eq(js_intern!(0.0)));
+		assert!(intern_ordering(Ordering::Greater).ptr_eq(js_intern!(1.0)));
+		assert!(intern_ordering(2.cmp(&1)).ptr_eq(&intern_ordering(Ordering::Greater)));
+		assert_eq!(intern_ordering(Ordering::Less).as_f64(), Some(-1.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_str_lossy() {
+		let lossy = intern_str_lossy(b"bad \xF0\x90\x80 bytes");
+		assert_eq!(lossy.as_string(), Some(String::from("bad \u{FFFD} bytes")));
+		assert!(lossy.ptr_eq(&intern_str_lossy(b"bad \xF0\x90\x80 bytes")));
+		assert!(lossy.ptr_eq(js_intern!("bad \u{FFFD} bytes")));
+		assert!(intern_str_lossy(b"good").ptr_eq(js_intern!("good")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_build_interner_with_capacity() {
+		let interner = Interner::builder().capacity(10_000).build();
+		assert!(interner.capacity() >= 10_000);
+		assert!(Interner::new().capacity() < 10_000);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_in_interner() {
+		let interner = Interner::new();
+		assert_eq!(interner.intern_str("instance") as *const _, interner.intern_str("instance") as *const _);
+		assert_ne!(interner.intern_str("instance") as *const _, js_intern!("instance") as *const _);
+		assert_eq!(interner.intern_f64(2.5).as_f64(), Some(2.5));
+		assert_eq!(interner.intern_bool(true) as *const _, interner.intern_bool(true) as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_convert_char_case() {
+		assert_eq!(intern_char_upper('ß').as_string(), Some(String::from("SS")));
+		assert_eq!(intern_char_upper('a').as_string(), Some(String::from("A")));
+		assert_eq!(intern_char_lower('A').as_string(), Some(String::from("a")));
+		assert_eq!(intern_char_lower('İ').as_string(), Some(String::from("i\u{307}")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_char_case() {
+		assert!(intern_char_upper('ß').ptr_eq(&intern_char_upper('ß')));
+		assert!(!intern_char_upper('a').ptr_eq(&intern_char_lower('a')));
+	}
+
+	// Unwinding isn't available under wasm, so this runs as a native test. It panics before calling into JavaScript.
+	#[cfg(debug_assertions)]
+	#[test]
+	fn reports_caller_location() {
+		let line = line!() + 1;
+		let result = std::panic::catch_unwind(|| intern_bin(1.0, 0.0, 0.0));
+		let message = result.unwrap_err().downcast::<String>().unwrap();
+		assert!(message.contains(&format!("{}:{}:", file!(), line)), "{}", message);
+	}
+
+	thread_local!(
+		static CONVERTED: std::cell::RefCell<Vec<InternKey>> = std::cell::RefCell::default();
+	);
+
+	fn recording_factory(key: InternKey) -> JsValue {
+		CONVERTED.with(|c| c.borrow_mut().push(key));
+		JsValue::UNDEFINED
+	}
+
+	// The factory doesn't need a JavaScript engine, so this runs natively.
+	#[test]
+	fn interner_converts_once_per_key() {
+		let interner = Interner::builder().factory(recording_factory).build();
+		interner.intern_str("a");
+		interner.intern_str("b");
+		interner.intern_str("a");
+		interner.intern_f64(1.0);
+		interner.intern_f64(1.0);
+		interner.intern_f64(-0.0);
+		interner.intern_bool(true);
+		interner.intern_bool(true);
+		let converted = CONVERTED.with(|c| c.borrow().clone());
+		assert_eq!(converted, vec![
+			InternKey::Str("a"),
+			InternKey::Str("b"),
+			InternKey::F64(1.0),
+			InternKey::F64(-0.0),
+			InternKey::Bool(true),
+		]);
+		assert_eq!(interner.intern_str("a") as *const _, interner.intern_str("a") as *const _);
+	}
+
+	#[test]
+	fn can_iterate_interner() {
+		let interner = Interner::builder().factory(|_| JsValue::UNDEFINED).build();
+		assert_eq!(interner.iter().count(), 0);
+		interner.intern_str("x");
+		interner.intern_str("y");
+		interner.intern_f64(2.5);
+		interner.intern_bool(false);
+		let entries: Vec<(InternKey, *const JsValue)> = interner.iter().map(|(key, value)| (key, value as *const _)).collect();
+		assert_eq!(entries.len(), 4);
+		for &(key, value) in entries.iter() {
+			let expected = match key {
+				InternKey::Str(s) => interner.intern_str(s),
+				InternKey::F64(number) => interner.intern_f64(number),
+				InternKey::Bool(b) => interner.intern_bool(b),
+			};
+			assert_eq!(value, expected as *const _);
+		}
+		let mut strings: Vec<&str> = entries.iter().filter_map(|&(key, _)| match key {
+			InternKey::Str(s) => Some(s),
+			_ => None,
+		}).collect();
+		strings.sort();
+		assert_eq!(strings, vec!["x", "y"]);
+		assert_eq!(entries[2].0, InternKey::F64(2.5));
+		assert_eq!(entries[3].0, InternKey::Bool(false));
+	}
+
+	#[derive(Default)]
+	struct XorHasher(u64);
+
+	impl std::hash::Hasher for XorHasher {
+		fn finish(&self) -> u64 {
+			self.0
+		}
+
+		fn write(&mut self, bytes: &[u8]) {
+			for &byte in bytes {
+				self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+			}
+		}
+	}
+
+	#[test]
+	fn interner_uses_custom_hasher() {
+		let interner: Interner<std::hash::BuildHasherDefault<XorHasher>> = Interner::builder()
+			.factory(|_| JsValue::UNDEFINED)
+			.hasher(std::hash::BuildHasherDefault::default())
+			.capacity(4)
+			.build();
+		assert_eq!(interner.intern_str("x") as *const _, interner.intern_str("x") as *const _);
+		assert_ne!(interner.intern_str("x") as *const _, interner.intern_str("y") as *const _);
+		assert_eq!(interner.intern_f64(1.5) as *const _, interner.intern_f64(1.5) as *const _);
+		assert_eq!(interner.iter().count(), 3);
+	}
+
+	const ZOOM_LEVELS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+	#[wasm_bindgen_test]
+	fn snaps_f64_to_set() {
+		assert_eq!(intern_f64_from_set(1.2, &ZOOM_LEVELS).as_f64(), Some(1.0));
+		assert_eq!(intern_f64_from_set(100.0, &ZOOM_LEVELS).as_f64(), Some(4.0));
+		assert_eq!(intern_f64_from_set(-3.0, &ZOOM_LEVELS).as_f64(), Some(0.5));
+		assert_eq!(intern_f64_from_set(1.5, &ZOOM_LEVELS).as_f64(), Some(1.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_f64_from_set() {
+		assert!(intern_f64_from_set(2.9, &ZOOM_LEVELS).ptr_eq(&intern_f64_from_set(2.1, &ZOOM_LEVELS)));
+		assert!(intern_f64_from_set(2.9, &ZOOM_LEVELS).ptr_eq(js_intern!(2.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_bool_jsarray() {
+		let array: &Array = intern_bool_jsarray(&[true, false, true]).as_js_value().dyn_ref().unwrap();
+		assert_eq!(array.length(), 3);
+		for flag in array.iter() {
+			assert_eq!(flag.js_typeof().as_string(), Some(String::from("boolean")));
+		}
+		assert_eq!(array.get(1).as_bool(), Some(false));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_bool_jsarray() {
+		static FIRST: [bool; 2] = [false, true];
+		static SECOND: [bool; 2] = [false, true];
+		assert!(intern_bool_jsarray(&FIRST).ptr_eq(&intern_bool_jsarray(&SECOND)));
+		assert!(!intern_bool_jsarray(&FIRST).ptr_eq(&intern_bool_jsarray(&FIRST[..1])));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_after_reset() {
+		fn site() -> &'static JsValue {
+			js_intern!("before reset")
+		}
+		assert_eq!(site() as *const _, js_intern!("before reset") as *const _);
+		intern_indexed(7, "seven");
+		unsafe { reset_interner(); }
+		assert!(get_indexed(7).is_none());
+		assert_eq!(site().as_string(), Some(String::from("before reset")));
+		assert_eq!(site() as *const _, js_intern!("before reset") as *const _);
+		assert_eq!(js_intern!(7u8) as *const _, js_intern!(7.0) as *const _);
+		intern_debug_assert_interned!(site());
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_option_char() {
+		assert_eq!(js_intern!(Some('a')).as_string(), Some(String::from("a")));
+		assert_eq!(js_intern!(Some('ß')).as_string(), Some(String::from("ß")));
+		assert!(js_intern!(None::<char>).is_undefined());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_option_char() {
+		assert_eq!(js_intern!(Some('a')) as *const _, js_intern!("a") as *const _);
+		assert_eq!(js_intern!(None::<char>) as *const _, js_intern!(None::<char>) as *const _);
+		intern_debug_assert_interned!(js_intern!(None::<char>));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_round_trip_subnormal() {
+		let subnormal = f64::MIN_POSITIVE / 2.0;
+		assert!(subnormal.is_subnormal());
+		assert_eq!(js_intern!(subnormal).as_f64().map(f64::to_bits), Some(subnormal.to_bits()));
+		assert_eq!(js_intern!(f64::from_bits(1)).as_f64().map(f64::to_bits), Some(1));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_subnormal() {
+		let subnormal = f64::MIN_POSITIVE / 2.0;
+		assert_eq!(js_intern!(subnormal) as *const _, js_intern!(f64::MIN_POSITIVE / 2.0) as *const _);
+		assert_ne!(js_intern!(subnormal) as *const _, js_intern!(f64::MIN_POSITIVE) as *const _);
+		assert_ne!(js_intern!(subnormal) as *const _, js_intern!(0.0) as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_tokens() {
+		let tokens: Vec<_> = intern_tokens("let x = 1;  let y", &[' ', ';']).iter().map(|t| t.as_string().unwrap()).collect();
+		assert_eq!(tokens, vec!["let", "x", "=", "1", "let", "y"]);
+		assert!(intern_tokens("", &[' ']).is_empty());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_tokens() {
+		let tokens = intern_tokens("a,b,a", &[',']);
+		assert!(tokens[0].ptr_eq(&tokens[2]));
+		assert!(tokens[1].ptr_eq(js_intern!("b")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_ratio() {
+		assert_eq!(intern_ratio(3, 4).as_f64(), Some(0.75));
+		assert_eq!(intern_ratio(-1, 8).as_f64(), Some(-0.125));
+		assert_eq!(intern_ratio(1, 0).as_f64(), Some(f64::INFINITY));
+		assert_eq!(intern_ratio(-1, 0).as_f64(), Some(f64::NEG_INFINITY));
+		assert!(intern_ratio(0, 0).as_f64().unwrap().is_nan());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_ratio() {
+		assert!(intern_ratio(1, 2).ptr_eq(&intern_ratio(2, 4)));
+		assert!(intern_ratio(1, 2).ptr_eq(js_intern!(0.5)));
+		assert!(intern_ratio(5, 0).ptr_eq(js_intern!(f64::INFINITY)));
+		assert!(intern_ratio(0, 0).ptr_eq(js_intern!(f64::NAN)));
+		assert!(intern_ratio(0, -5).ptr_eq(&intern_ratio(0, 5)));
+		assert!(intern_ratio(0, -5).ptr_eq(js_intern!(0.0)));
+		// The operands don't convert to f64 exactly, so dividing them as they are would round differently than 1 / 3.
+		let large = (1 << 53) + 1;
+		assert!(intern_ratio(large, 3 * large).ptr_eq(&intern_ratio(1, 3)));
+		assert!(intern_ratio(i64::MIN, i64::MIN).ptr_eq(js_intern!(1.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_codepoint_str() {
+		assert_eq!(intern_codepoint_str(0x41).unwrap().as_string(), Some(String::from("A")));
+		assert_eq!(intern_codepoint_str(0x1F600).unwrap().as_string(), Some(String::from("\u{1F600}")));
+		assert!(intern_codepoint_str(0xD800).is_none());
+		assert!(intern_codepoint_str(0x110000).is_none());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_codepoint_str() {
+		assert!(intern_codepoint_str(0x41).unwrap().ptr_eq(js_intern!("A")));
+		assert!(intern_codepoint_str(0x41).unwrap().ptr_eq(js_intern!(Some('A'))));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_strict() {
+		assert_eq!(js_intern_strict!("strict") as *const _, js_intern!("strict") as *const _);
+		assert_eq!(js_intern_strict!(-2.5).as_f64(), Some(-2.5));
+		assert_eq!(js_intern_strict!(true).as_bool(), Some(true));
+	}
+
+	fn same_from_first_site() -> &'static JsValue {
+		js_intern!("same")
+	}
+
+	fn same_from_second_site() -> &'static JsValue {
+		js_intern!("same")
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_across_call_sites() {
+		assert_eq!(same_from_first_site() as *const _, same_from_second_site() as *const _);
+		// Again, now that both sites have cached their pointers.
+		assert_eq!(same_from_first_site() as *const _, same_from_second_site() as *const _);
+	}
+
+	#[derive(Copy, Clone)]
+	struct UserId(u64);
+
+	#[derive(Copy, Clone)]
+	struct PostId(u64);
+
+	#[wasm_bindgen_test]
+	fn can_intern_id() {
+		assert_eq!(js_intern_id!(UserId, UserId(42)).as_f64(), Some(42.0));
+		let user = UserId(7);
+		assert_eq!(js_intern_id!(UserId, user).as_f64(), Some(7.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_id_per_type() {
+		assert_eq!(js_intern_id!(UserId, UserId(1)) as *const _, js_intern_id!(UserId, UserId(1)) as *const _);
+		assert_ne!(js_intern_id!(UserId, UserId(1)) as *const _, js_intern_id!(PostId, PostId(1)) as *const _);
+		assert_ne!(js_intern_id!(UserId, UserId(1)) as *const _, js_intern_id!(UserId, UserId(2)) as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn integer_valued_floats_are_integers() {
+		let values = [
+			js_intern!(3.0),
+			js_intern!(-0.0),
+			js_intern!(3.0f32),
+			js_intern!(3i32),
+			js_intern!(300u16),
+			js_intern!(Wrapping(3u8)),
+			js_intern!(Ordering::Greater),
+			intern_ratio(6, 2).as_js_value(),
+			js_intern!(9_007_199_254_740_992.0),
+		];
+		for value in values.iter() {
+			assert!(Number::is_integer(value), "{:?}", value);
+		}
+		assert!(!Number::is_integer(js_intern!(3.5)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_joined() {
+		static ITEMS: [&str; 3] = ["a", "b", "c"];
+		assert_eq!(intern_joined(&ITEMS, ", ").as_string(), Some(String::from("a, b, c")));
+		assert_eq!(intern_joined(&ITEMS, "").as_string(), Some(String::from("abc")));
+		assert_eq!(intern_joined(&[], ", ").as_string(), Some(String::new()));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_joined() {
+		static ITEMS: [&str; 2] = ["x", "y"];
+		assert!(intern_joined(&ITEMS, "-").ptr_eq(&intern_joined(&ITEMS, "-")));
+		assert!(!intern_joined(&ITEMS, "-").ptr_eq(&intern_joined(&ITEMS, "+")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_runtime_bool() {
+		use std::sync::atomic::{self, AtomicBool};
+		let flag = AtomicBool::new(true);
+		assert_eq!(intern_bool(flag.load(atomic::Ordering::Relaxed)).as_bool(), Some(true));
+		flag.store(false, atomic::Ordering::Relaxed);
+		assert_eq!(intern_bool(flag.load(atomic::Ordering::Relaxed)).as_bool(), Some(false));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_runtime_bool() {
+		assert!(intern_bool(true).ptr_eq(js_intern!(true)));
+		assert!(intern_bool(false).ptr_eq(js_intern!(false)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_string() {
+		assert_eq!(js_intern!(String::from("owned")).as_string(), Some(String::from("owned")));
+		let name = String::from("runtime");
+		assert_eq!(js_intern!(name).as_string(), Some(String::from("runtime")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_string() {
+		assert_eq!(js_intern!(String::from("shared")) as *const _, js_intern!("shared") as *const _);
+		assert!(intern_str_lossy(b"shared").ptr_eq(js_intern!(String::from("shared"))));
+		assert!(intern_str_lossy(b"shared \xFF").ptr_eq(js_intern!(String::from("shared \u{FFFD}"))));
+	}
+
+	#[wasm_bindgen_test]
+	fn interns_domstring_as_primitive() {
+		let value = intern_domstring("primitive");
+		assert_eq!(value.js_typeof().as_string(), Some(String::from("string")));
+		assert!(!value.is_object());
+		let string: &JsString = value.dyn_ref().unwrap();
+		assert_eq!(string.length(), 9);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_format_value_unit() {
+		assert_eq!(intern_value_unit(42.0, "px").as_string(), Some(String::from("42px")));
+		assert_eq!(intern_value_unit(100.0, "%").as_string(), Some(String::from("100%")));
+		assert_eq!(intern_value_unit(1.5, "em").as_string(), Some(format!("{}{}", 1.5, "em")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_value_unit() {
+		assert!(intern_value_unit(42.0, "px").ptr_eq(&intern_value_unit(42.0, "px")));
+		assert!(!intern_value_unit(42.0, "px").ptr_eq(&intern_value_unit(42.0, "em")));
+		assert!(!intern_value_unit(42.0, "px").ptr_eq(&intern_value_unit(43.0, "px")));
+	}
+
+	#[wasm_bindgen_test]
+	fn allocates_once_per_distinct_value() {
+		assert!(count_allocations(|| intern_domstring("allocated once")) > 0);
+		assert_eq!(count_allocations(|| intern_domstring("allocated once")), 0);
+		count_allocations(|| intern_bool(true));
+		assert_eq!(count_allocations(|| intern_bool(true)), 0);
+		count_allocations(|| intern_ratio(22, 7));
+		assert_eq!(count_allocations(|| intern_ratio(22, 7)), 0);
+	}
+
+	#[wasm_bindgen_test]
+	fn interns_cached_strings_without_allocating() {
+		use js_intern_core::CacheJsIntern__;
+		let first = String::from("owned hit").cache_js_intern__();
+		let owned = String::from("owned hit");
+		assert_eq!(count_allocations(|| assert_eq!(owned.cache_js_intern__(), first)), 0);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_cow_str() {
+		for s in [Cow::Borrowed("cow"), Cow::Owned(String::from("moo"))] {
+			let expected = s.to_string();
+			assert_eq!(js_intern_cow!(s).as_string(), Some(expected));
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_cow_str() {
+		assert_eq!(js_intern_cow!(Cow::Borrowed("cow")) as *const _, js_intern!("cow") as *const _);
+		assert_eq!(js_intern_cow!(Cow::Owned(String::from("cow"))) as *const _, js_intern!("cow") as *const _);
+		assert!(intern_cow_str(Cow::Owned(String::from("owned cow"))).ptr_eq(js_intern!("owned cow")));
+	}
+
+	#[cfg(feature = "graphemes")]
+	#[wasm_bindgen_test]
+	fn can_count_graphemes() {
+		let (value, count) = intern_str_with_graphemes("a\u{1F44D}\u{1F3FD}e\u{301}");
+		assert_eq!(value.as_string(), Some(String::from("a\u{1F44D}\u{1F3FD}e\u{301}")));
+		assert_eq!(count, 3);
+		assert!(value.ptr_eq(js_intern!("a\u{1F44D}\u{1F3FD}e\u{301}")));
+		assert_eq!(intern_str_with_graphemes("").1, 0);
+	}
+
+	#[wasm_bindgen_test]
+	fn nan_has_identity_where_equality_fails() {
+		assert_ne!(js_intern!(f64::NAN), js_intern!(f64::NAN));
+		assert_eq!(js_intern!(f64::NAN) as *const _, js_intern!(f64::NAN) as *const _);
+		assert_eq!(js_intern!(f64::NAN) as *const _, intern_ratio(0, 0).as_js_value() as *const _);
+		assert_eq!(js_intern!(f64::INFINITY), js_intern!(f64::INFINITY));
+		assert_eq!(js_intern!(f64::NEG_INFINITY) as *const _, intern_ratio(-1, 0).as_js_value() as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_format_bytes_hex() {
+		assert_eq!(intern_bytes_hex(&[0x00, 0x0f, 0xa0, 0xff]).as_string(), Some(String::from("000fa0ff")));
+		assert_eq!(intern_bytes_hex(&[]).as_string(), Some(String::new()));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_bytes_hex() {
+		static FIRST: [u8; 2] = [0xde, 0xad];
+		static SECOND: [u8; 2] = [0xde, 0xad];
+		assert!(intern_bytes_hex(&FIRST).ptr_eq(&intern_bytes_hex(&SECOND)));
+		assert!(!intern_bytes_hex(&FIRST).ptr_eq(&intern_bytes_hex(&FIRST[..1])));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_range() {
+		let array: &Array = intern_range(-2..5).as_js_value().dyn_ref().unwrap();
+		assert_eq!(array.length(), 2);
+		assert_eq!(array.get(0).as_f64(), Some(-2.0));
+		assert_eq!(array.get(1).as_f64(), Some(5.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_range() {
+		assert!(intern_range(0..10).ptr_eq(&intern_range(0..10)));
+		assert!(!intern_range(0..10).ptr_eq(&intern_range(0..11)));
+	}
+
+	#[wasm_bindgen_test]
+	fn epoch_increases_after_reset() {
+		let before = intern_epoch();
+		assert_eq!(intern_epoch(), before);
+		unsafe { reset_interner(); }
+		assert_eq!(intern_epoch(), before + 1);
+		unsafe { reset_interner(); }
+		assert!(intern_epoch() > before + 1);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_f32_slice() {
+		let array: &Float32Array = intern_f32_slice(&[0.5, -1.0, 0.1]).as_js_value().dyn_ref().unwrap();
+		assert_eq!(array.to_vec(), vec![0.5, -1.0, 0.1]);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_f32_slice() {
+		static FIRST: [f32; 3] = [1.0, 2.0, 3.0];
+		static SECOND: [f32; 3] = [1.0, 2.0, 3.0];
+		assert!(intern_f32_slice(&FIRST).ptr_eq(&intern_f32_slice(&SECOND)));
+		assert!(!intern_f32_slice(&[0.0]).ptr_eq(&intern_f32_slice(&[-0.0])));
+	}
+
+	// Inputs which are easy to get wrong when converting between utf-8 and utf-16.
+	const STRING_SEEDS: [&str; 10] = [
+		"",
+		"\0",
+		"nul \0 inside",
+		"\u{7F}\u{80}\u{7FF}\u{800}",
+		"\u{D7FF}\u{E000}\u{FFFD}\u{FFFF}",
+		"\u{10000}\u{10FFFF}",
+		"\u{1F600}",
+		"\u{e9}",
+		"e\u{301}",
+		"\u{FEFF}bom",
+	];
+
+	/// Generates arbitrary strings from a fixed seed, so that failures are reproducible.
+	fn arbitrary_strings(count: usize) -> Vec<String> {
+		let mut state = 0x2545_f491_4f6c_dd1du64;
+		let mut next = move || {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state
+		};
+		(0..count).map(|_| {
+			let len = next() % 16;
+			(0..len).filter_map(|_| {
+				// Favor the edges of each utf-8 encoding length, where bugs tend to be.
+				let edges = [0u32, 0x7f, 0x80, 0x7ff, 0x800, 0xd7ff, 0xe000, 0xffff, 0x10000, 0x10ffff];
+				let base = edges[(next() % edges.len() as u64) as usize];
+				let offset = (next() % 3) as u32;
+				std::char::from_u32(base.saturating_add(offset)).or_else(|| std::char::from_u32(base.saturating_sub(offset)))
+			}).collect()
+		}).collect()
+	}
+
+	#[wasm_bindgen_test]
+	fn strings_round_trip() {
+		let inputs = STRING_SEEDS.iter().map(|&s| String::from(s)).chain(arbitrary_strings(500));
+		for input in inputs {
+			let value = intern_cow_str(Cow::Owned(input.clone()));
+			assert_eq!(value.as_string().as_ref(), Some(&input), "{:?}", input);
+			assert!(value.ptr_eq(&intern_cow_str(Cow::Owned(input.clone()))), "{:?}", input);
+		}
+		// Strings are compared exactly, without normalization.
+		assert!(!intern_cow_str(Cow::Borrowed(STRING_SEEDS[7])).ptr_eq(&intern_cow_str(Cow::Borrowed(STRING_SEEDS[8]))));
+	}
+
+	#[wasm_bindgen_test]
+	fn compares_interned_nums() {
+		assert!(interned_num_eq(js_intern!(f64::NAN), js_intern!(f64::NAN)));
+		assert!(interned_num_eq(js_intern!(f64::NAN), &JsValue::from_f64(f64::NAN)));
+		assert!(!interned_num_eq(js_intern!(0.0), js_intern!(-0.0)));
+		assert!(interned_num_eq(js_intern!(1.5), js_intern!(1.5)));
+		assert!(interned_num_eq(js_intern!(2u8), &JsValue::from_f64(2.0)));
+		assert!(!interned_num_eq(js_intern!(1.5), js_intern!(2.5)));
+		assert!(!interned_num_eq(js_intern!("1.5"), &JsValue::from_str("1.5")));
+		assert!(interned_num_eq(js_intern!("1.5"), js_intern!("1.5")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_ascii_only() {
+		assert_eq!(intern_str_ascii_only("ascii ~").unwrap().as_string(), Some(String::from("ascii ~")));
+		assert!(intern_str_ascii_only("caf\u{e9}").is_none());
+		assert!(intern_str_ascii_only("").is_some());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_ascii_only() {
+		assert!(intern_str_ascii_only("ascii").unwrap().ptr_eq(js_intern!("ascii")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_kv() {
+		let pair = intern_kv("color", "red");
+		assert_eq!(Reflect::get(&pair, js_intern!("key")).unwrap().as_string(), Some(String::from("color")));
+		assert_eq!(Reflect::get(&pair, js_intern!("value")).unwrap().as_string(), Some(String::from("red")));
+		let object: &Object = pair.as_js_value().dyn_ref().unwrap();
+		assert_eq!(Object::keys(object).length(), 2);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_kv() {
+		assert!(intern_kv("color", "red").ptr_eq(&intern_kv("color", "red")));
+		assert!(!intern_kv("color", "red").ptr_eq(&intern_kv("red", "color")));
+		let value = Reflect::get(&intern_kv("color", "red"), js_intern!("value")).unwrap();
+		assert_eq!(value, *js_intern!("red"));
+	}
+
+	#[wasm_bindgen_test]
+	fn reuses_approx_f64() {
+		assert_eq!(intern_f64_approx(1.0, 1e-6).as_f64(), Some(1.0));
+		assert_eq!(intern_f64_approx(1.000_000_1, 1e-6).as_f64(), Some(1.0));
+		assert_eq!(intern_f64_approx(1.1, 1e-6).as_f64(), Some(1.1));
+		assert!(intern_f64_approx(f64::NAN, 1e-6).as_f64().unwrap().is_nan());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_approx_f64() {
+		assert!(intern_f64_approx(20.0, 1e-6).ptr_eq(&intern_f64_approx(19.999_999_9, 1e-6)));
+		assert!(intern_f64_approx(20.0, 1e-6).ptr_eq(js_intern!(20.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_object() {
+		let object = js_intern_object!{ name: "x", count: 3 };
+		assert_eq!(Reflect::get(object, js_intern!("name")).unwrap().as_string(), Some(String::from("x")));
+		assert_eq!(Reflect::get(object, js_intern!("count")).unwrap().as_f64(), Some(3.0));
+		assert_eq!(Object::keys(object.unchecked_ref::<Object>()).length(), 2);
+		assert_eq!(Object::keys(js_intern_object!{}.unchecked_ref::<Object>()).length(), 0);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_object() {
+		let first = js_intern_object!{ name: "x", count: 3 };
+		let second = js_intern_object!{ name: "x", count: 3, };
+		assert_eq!(first as *const _, second as *const _);
+		assert_ne!(first as *const _, js_intern_object!{ name: "x", count: 4 } as *const _);
+		assert_ne!(first as *const _, js_intern_object!{ count: 3, name: "x" } as *const _);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_utf16_unit() {
+		let units: Vec<u16> = "a\u{1F600}".encode_utf16().collect();
+		let interned: Vec<_> = units.iter().map(|&unit| intern_utf16_unit(unit).as_f64()).collect();
+		assert_eq!(interned, vec![Some(97.0), Some(f64::from(0xD83Du16)), Some(f64::from(0xDE00u16))]);
+		assert_eq!(intern_utf16_unit(u16::MAX).as_f64(), Some(65535.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_utf16_unit() {
+		assert!(intern_utf16_unit(0xD83D).ptr_eq(&intern_utf16_unit(0xD83D)));
+		assert!(intern_utf16_unit(97).ptr_eq(js_intern!(97u16)));
+		assert!(intern_utf16_unit(97).ptr_eq(js_intern!(97.0)));
+	}
+
+	thread_local!(
+		static MISSED: std::cell::RefCell<Vec<String>> = std::cell::RefCell::default();
+	);
+
+	fn record_miss(s: &str) {
+		MISSED.with(|m| m.borrow_mut().push(String::from(s)));
+	}
+
+	#[wasm_bindgen_test]
+	fn calls_back_once_per_new_string() {
+		on_string_miss(Some(record_miss));
+		intern_domstring("first miss");
+		intern_domstring("first miss");
+		intern_cow_str(Cow::Owned(String::from("second miss")));
+		intern_cow_str(Cow::Owned(String::from("first miss")));
+		let _ = js_intern!(String::from("second miss"));
+		on_string_miss(None);
+		intern_domstring("after unset");
+		assert_eq!(MISSED.with(|m| m.borrow().clone()), vec!["first miss", "second miss"]);
+	}
+
+	fn intern_on_miss(s: &str) {
+		if s == "outer miss" {
+			let _ = js_intern!("inner miss");
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_strings_from_miss_callback() {
+		on_string_miss(Some(intern_on_miss));
+		let outer = intern_domstring("outer miss");
+		on_string_miss(None);
+		assert!(outer.ptr_eq(&intern_domstring("outer miss")));
+		assert!(get_interned_str("inner miss").is_some());
+	}
+
+	static SET_ON_MISS: [&str; 2] = ["set member miss", "other member miss"];
+
+	fn intern_kv_on_miss(s: &str) {
+		if s == "set member miss" {
+			assert!(intern_kv("hook key", "hook value").is_object());
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_objects_from_miss_callback_while_building_objects() {
+		on_string_miss(Some(intern_kv_on_miss));
+		let set = intern_string_set(&SET_ON_MISS);
+		on_string_miss(None);
+		assert!(set.ptr_eq(&intern_string_set(&SET_ON_MISS)));
+		assert!(get_interned_str("hook value").is_some());
+	}
+
+	#[wasm_bindgen_test]
+	fn refuses_strings_over_limit() {
+		// Start from an empty cache, so the limit counts only the strings below.
+		unsafe { reset_interner(); }
+		set_string_cache_limit(Some(2));
+		let first = try_intern_str("limit 1");
+		let second = try_intern_str("limit 2");
+		let third = try_intern_str("limit 3");
+		let again = try_intern_str("limit 1");
+		set_string_cache_limit(None);
+		assert!(first.is_ok() && second.is_ok());
+		assert_eq!(third.unwrap_err(), CacheFull { limit: 2 });
+		assert!(again.unwrap().ptr_eq(js_intern!("limit 1")));
+		assert!(try_intern_str("limit 3").is_ok());
+	}
+
+	#[wasm_bindgen_test]
+	fn can_convert_degrees_to_radians() {
+		for &degrees in [0.0f64, 45.0, 90.0, -180.0, 360.0].iter() {
+			assert_eq!(intern_radians_from_degrees(degrees).as_f64(), Some(degrees.to_radians()));
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_radians_from_degrees() {
+		assert!(intern_radians_from_degrees(90.0).ptr_eq(&intern_radians_from_degrees(90.0)));
+		assert!(intern_radians_from_degrees(180.0).ptr_eq(js_intern!(std::f64::consts::PI)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_str_utf8_bytes() {
+		let array: &Uint8Array = intern_str_utf8_bytes("h\u{e9}!").as_js_value().dyn_ref().unwrap();
+		assert_eq!(array.to_vec(), vec![b'h', 0xc3, 0xa9, b'!']);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_str_utf8_bytes() {
+		static TEXT: &str = "encoded";
+		assert!(intern_str_utf8_bytes(TEXT).ptr_eq(&intern_str_utf8_bytes(TEXT)));
+		assert!(!intern_str_utf8_bytes(TEXT).ptr_eq(&intern_str_utf8_bytes(&TEXT[1..])));
+	}
+
+	#[cfg(feature = "graphemes")]
+	#[wasm_bindgen_test]
+	fn can_reverse_str() {
+		assert_eq!(intern_str_reversed("a\u{1F44D}\u{1F3FD}e\u{301}").as_string(), Some(String::from("e\u{301}\u{1F44D}\u{1F3FD}a")));
+		assert_eq!(intern_str_reversed("").as_string(), Some(String::new()));
+	}
+
+	#[cfg(feature = "graphemes")]
+	#[wasm_bindgen_test]
+	fn deduplicates_reversed_str() {
+		static TEXT: &str = "stressed";
+		assert!(intern_str_reversed(TEXT).ptr_eq(&intern_str_reversed(TEXT)));
+		assert_eq!(intern_str_reversed(TEXT).as_string(), Some(String::from("desserts")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_bool_pair() {
+		let (value, negated) = intern_bool_pair(true);
+		assert_eq!((value.as_bool(), negated.as_bool()), (Some(true), Some(false)));
+		let (value, negated) = intern_bool_pair(false);
+		assert_eq!((value.as_bool(), negated.as_bool()), (Some(false), Some(true)));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_bool_pair() {
+		let (value, negated) = intern_bool_pair(true);
+		assert!(value.ptr_eq(js_intern!(true)));
+		assert!(negated.ptr_eq(js_intern!(false)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_quiet_nan() {
+		assert!(intern_quiet_nan().as_f64().unwrap().is_nan());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_quiet_nan() {
+		assert!(intern_quiet_nan().ptr_eq(&intern_quiet_nan()));
+		assert!(intern_quiet_nan().ptr_eq(js_intern!(f64::NAN)));
+		assert!(!intern_quiet_nan().ptr_eq(js_intern!(f64::from_bits(f64::NAN.to_bits() | 1))));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_round_trip_string_ids() {
+		let mut table = StringIdTable::new();
+		assert!(table.is_empty());
+		assert_eq!(table.intern("get"), 0);
+		assert_eq!(table.intern("post"), 1);
+		assert_eq!(table.intern("get"), 0);
+		assert_eq!(table.len(), 2);
+		assert_eq!(table.id_of(&String::from("post")), Some(1));
+		assert_eq!(table.id_of("put"), None);
+		assert_eq!(table.str_of(0), Some("get"));
+		assert_eq!(table.str_of(2), None);
+		assert_eq!(table.js_of(1).unwrap().as_string(), Some(String::from("post")));
+		assert!(table.js_of(2).is_none());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_string_id_table() {
+		let mut table = StringIdTable::new();
+		let id = table.intern("route");
+		assert!(table.js_of(id).unwrap().ptr_eq(js_intern!("route")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_get_interned_str() {
+		assert!(get_interned_str("not interned yet").is_none());
+		assert!(get_interned_str("not interned yet").is_none());
+		let value = js_intern!("now interned");
+		assert!(get_interned_str(&String::from("now interned")).unwrap().ptr_eq(value));
+	}
+
+	#[wasm_bindgen_test]
+	fn get_interned_str_finds_every_interned_str() {
+		let strings = arbitrary_strings(2000);
+		for s in strings.iter() {
+			intern_cow_str(Cow::Owned(s.clone()));
+		}
+		for s in strings.iter() {
+			assert!(get_interned_str(s).unwrap().ptr_eq(&intern_cow_str(Cow::Owned(s.clone()))), "{:?}", s);
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_frame() {
+		assert_eq!(intern_frame(0.0, 16.0).as_f64(), Some(0.0));
+		assert_eq!(intern_frame(33.3, 16.0).as_f64(), Some(2.0));
+		assert_eq!(intern_frame(-1.0, 16.0).as_f64(), Some(-1.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_frames() {
+		assert!(intern_frame(100.0, 1000.0 / 60.0).ptr_eq(&intern_frame(110.0, 1000.0 / 60.0)));
+		assert!(!intern_frame(100.0, 1000.0 / 60.0).ptr_eq(&intern_frame(120.0, 1000.0 / 60.0)));
+		assert!(intern_frame(-0.0, 16.0).ptr_eq(js_intern!(0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_frozen_str_array() {
+		static ITEMS: &[&str] = &["north", "south"];
+		let array: &Array = intern_str_array_frozen(ITEMS).as_js_value().dyn_ref().unwrap();
+		assert!(Object::is_frozen(array));
+		assert_eq!(Reflect::set(array, &JsValue::from(0), &JsValue::from_str("east")).ok(), Some(false));
+		assert_eq!(array.get(0).as_string(), Some(String::from("north")));
+		assert_eq!(array.length(), 2);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_frozen_str_array() {
+		static ITEMS: &[&str] = &["up", "down"];
+		assert!(intern_str_array_frozen(ITEMS).ptr_eq(&intern_str_array_frozen(ITEMS)));
+		assert!(!intern_str_array_frozen(ITEMS).ptr_eq(&intern_str_array_frozen(&ITEMS[1..])));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_parse_diagnostics_json() {
+		let _ = js_intern!("diagnosed \"quoted\"\n");
+		let _ = js_intern!(7.5);
+		let json = js_sys::JSON::parse(&intern_diagnostics_json(usize::MAX)).unwrap();
+		let field = |value: &JsValue, name: &str| Reflect::get(value, &JsValue::from_str(name)).unwrap();
+		assert_eq!(field(&json, "epoch").as_f64(), Some(intern_epoch() as f64));
+		assert!(field(&json, "string_limit").is_null());
+		let strings = field(&field(&json, "caches"), "strings");
+		let count = field(&strings, "count").as_f64().unwrap();
+		assert!(count >= 1.0);
+		assert!(field(&strings, "capacity").as_f64().unwrap() >= count);
+		let sample: Array = field(&strings, "sample").dyn_into().unwrap();
+		assert_eq!(sample.length() as f64, count);
+		assert!(sample.includes(&JsValue::from_str("diagnosed \"quoted\"\n"), 0));
+		assert!(field(&field(&field(&json, "caches"), "numbers"), "count").as_f64().unwrap() >= 1.0);
+	}
+
+	#[wasm_bindgen_test]
+	fn diagnostics_json_samples_at_most_the_requested_keys() {
+		let _ = js_intern!("sampled 1");
+		let _ = js_intern!("sampled 2");
+		let json = js_sys::JSON::parse(&intern_diagnostics_json(1)).unwrap();
+		let strings = Reflect::get(&Reflect::get(&json, &JsValue::from_str("caches")).unwrap(), &JsValue::from_str("strings")).unwrap();
+		let sample: Array = Reflect::get(&strings, &JsValue::from_str("sample")).unwrap().dyn_into().unwrap();
+		assert_eq!(sample.length(), 1);
+		let json = js_sys::JSON::parse(&intern_diagnostics_json(0)).unwrap();
+		assert!(json.is_object());
+	}
+
+	#[wasm_bindgen_test]
+	fn can_format_money() {
+		assert_eq!(intern_money_str(123456, "$").as_string(), Some(String::from("$1,234.56")));
+		assert_eq!(intern_money_str(0, "$").as_string(), Some(String::from("$0.00")));
+		assert_eq!(intern_money_str(-5, "$").as_string(), Some(String::from("-$0.05")));
+		assert_eq!(intern_money_str(100000000, "\u{20ac}").as_string(), Some(String::from("\u{20ac}1,000,000.00")));
+		assert_eq!(intern_money_str(99999, "").as_string(), Some(String::from("999.99")));
+		assert_eq!(intern_money_str(i64::MIN, "$").as_string(), Some(String::from("-$92,233,720,368,547,758.08")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_money_str() {
+		assert!(intern_money_str(250, "$").ptr_eq(&intern_money_str(250, "$")));
+		assert!(!intern_money_str(250, "$").ptr_eq(&intern_money_str(250, "\u{a3}")));
+		assert!(!intern_money_str(250, "$").ptr_eq(&intern_money_str(-250, "$")));
+	}
+
+	#[wasm_bindgen_test]
+	fn clears_string_cache_past_threshold() {
+		let _ = js_intern!("pressure 1");
+		let _ = js_intern!("pressure 2");
+		let number = js_intern!(4321.5) as *const JsValue;
+		let epoch = intern_epoch();
+		assert!(!unsafe { clear_string_cache_if_over(usize::MAX) });
+		assert!(get_interned_str("pressure 1").is_some());
+		assert_eq!(intern_epoch(), epoch);
+		assert!(unsafe { clear_string_cache_if_over(1) });
+		assert!(get_interned_str("pressure 1").is_none());
+		assert!(intern_epoch() > epoch);
+		assert_eq!(js_intern!(4321.5) as *const JsValue, number);
+		assert_eq!(js_intern!("pressure 2").as_string(), Some(String::from("pressure 2")));
+	}
+
+	#[wasm_bindgen_test]
+	fn clearing_string_cache_below_threshold_is_a_no_op() {
+		let value = js_intern!("below threshold") as *const JsValue;
+		assert!(!unsafe { clear_string_cache_if_over(usize::MAX) });
+		assert_eq!(js_intern!("below threshold") as *const JsValue, value);
+		assert!(get_interned_str("below threshold").unwrap().ptr_eq(js_intern!("below threshold")));
+	}
+
+	#[cfg(feature = "categories")]
+	#[wasm_bindgen_test]
+	fn can_intern_char_with_category() {
+		assert_eq!(intern_char_with_category('a').1, GeneralCategory::LowercaseLetter);
+		assert_eq!(intern_char_with_category('Q').1, GeneralCategory::UppercaseLetter);
+		assert_eq!(intern_char_with_category('7').1, GeneralCategory::DecimalNumber);
+		assert_eq!(intern_char_with_category(' ').1, GeneralCategory::SpaceSeparator);
+		assert_eq!(intern_char_with_category('(').1, GeneralCategory::OpenPunctuation);
+		assert_eq!(intern_char_with_category('a').0.as_string(), Some(String::from("a")));
+	}
+
+	#[cfg(feature = "categories")]
+	#[wasm_bindgen_test]
+	fn deduplicates_char_with_category() {
+		assert!(intern_char_with_category('+').0.ptr_eq(&intern_char_with_category('+').0));
+		assert!(intern_char_with_category('+').0.ptr_eq(js_intern!("+")));
+	}
+
+	#[cfg(feature = "width")]
+	#[wasm_bindgen_test]
+	fn can_intern_char_with_width() {
+		assert_eq!(intern_char_with_width('a').1, 1);
+		assert_eq!(intern_char_with_width('中').1, 2);
+		assert_eq!(intern_char_with_width('\u{301}').1, 0);
+		assert_eq!(intern_char_with_width('中').0.as_string(), Some(String::from("中")));
+	}
+
+	#[cfg(feature = "width")]
+	#[wasm_bindgen_test]
+	fn deduplicates_char_with_width() {
+		assert!(intern_char_with_width('界').0.ptr_eq(&intern_char_with_width('界').0));
+		assert!(intern_char_with_width('界').0.ptr_eq(js_intern!("界")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_hash_str() {
+		assert_eq!(intern_str_hashed("").1, 0xcbf2_9ce4_8422_2325);
+		assert_eq!(intern_str_hashed("a").1, 0xaf63_dc4c_8601_ec8c);
+		assert_ne!(intern_str_hashed("key 1").1, intern_str_hashed("key 2").1);
+		assert_eq!(intern_str_hashed("hashed").0.as_string(), Some(String::from("hashed")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_hashed_str() {
+		let (value, hash) = intern_str_hashed("stable");
+		assert!(value.ptr_eq(js_intern!("stable")));
+		assert_eq!(intern_str_hashed(String::from("stable").leak()).1, hash);
+		assert!(intern_str_hashed("stable").0.ptr_eq(&value));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_opt_bytes() {
+		let array: &Uint8Array = intern_opt_bytes(Some(&[1, 2, 3])).as_js_value().dyn_ref().unwrap();
+		assert_eq!(array.to_vec(), vec![1, 2, 3]);
+		assert!(intern_opt_bytes(None).is_undefined());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_opt_bytes() {
+		static BYTES: &[u8] = &[9, 8, 7];
+		assert!(intern_opt_bytes(Some(BYTES)).ptr_eq(&intern_bytes(BYTES)));
+		assert!(intern_opt_bytes(None).ptr_eq(js_intern!(None::<char>)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_num_json_safe() {
+		assert_eq!(intern_num_json_safe(1.5).as_f64(), Some(1.5));
+		assert_eq!(intern_num_json_safe(f64::NAN).as_string(), Some(String::from("NaN")));
+		assert_eq!(intern_num_json_safe(f64::INFINITY).as_string(), Some(String::from("Infinity")));
+		assert_eq!(intern_num_json_safe(f64::NEG_INFINITY).as_string(), Some(String::from("-Infinity")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_num_json_safe() {
+		assert!(intern_num_json_safe(-2.0).ptr_eq(js_intern!(-2.0)));
+		assert!(intern_num_json_safe(f64::NAN).ptr_eq(js_intern!("NaN")));
+		assert!(intern_num_json_safe(f64::from_bits(f64::NAN.to_bits() | 1)).ptr_eq(js_intern!("NaN")));
+		assert!(intern_num_json_safe(f64::INFINITY).ptr_eq(js_intern!("Infinity")));
+		assert!(intern_num_json_safe(f64::NEG_INFINITY).ptr_eq(js_intern!("-Infinity")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_normalize_paths() {
+		assert_eq!(intern_path("/a/").as_string(), Some(String::from("/a")));
+		assert_eq!(intern_path("/a/b//").as_string(), Some(String::from("/a/b")));
+		assert_eq!(intern_path("a/").as_string(), Some(String::from("a")));
+		assert_eq!(intern_path("/").as_string(), Some(String::from("/")));
+		assert_eq!(intern_path("///").as_string(), Some(String::from("/")));
+		assert_eq!(intern_path("").as_string(), Some(String::new()));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_paths() {
+		assert!(intern_path("/users/").ptr_eq(&intern_path("/users")));
+		assert!(intern_path("/users").ptr_eq(js_intern!("/users")));
+		assert!(intern_path("//").ptr_eq(js_intern!("/")));
+		assert!(!intern_path("/users").ptr_eq(&intern_path("/users/1")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_compute_stats_object() {
+		static DATA: &[f64] = &[3.0, -1.0, 4.0, 2.0];
+		let stats = intern_stats_object(DATA);
+		let field = |name: &str| Reflect::get(&stats, &JsValue::from_str(name)).unwrap().as_f64().unwrap();
+		assert_eq!((field("min"), field("max"), field("mean")), (-1.0, 4.0, 2.0));
+		let empty = intern_stats_object(&[]);
+		assert!(Reflect::get(&empty, &JsValue::from_str("mean")).unwrap().as_f64().unwrap().is_nan());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_stats_object() {
+		static DATA: &[f64] = &[1.0, 2.0];
+		assert!(intern_stats_object(DATA).ptr_eq(&intern_stats_object(DATA)));
+		assert!(!intern_stats_object(DATA).ptr_eq(&intern_stats_object(&DATA[1..])));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_round_to_nice_ticks() {
+		let cases = [(1.2, 1.0), (1.6, 2.0), (2.9, 2.0), (4.0, 5.0), (6.9, 5.0), (7.0, 10.0), (0.037, 0.05), (0.21, 0.2), (730.0, 1000.0), (1000.0, 1000.0), (-18.0, -20.0), (0.0, 0.0)];
+		for &(value, tick) in cases.iter() {
+			assert_eq!(intern_nice_tick(value).as_f64(), Some(tick), "{}", value);
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_nice_ticks() {
+		assert!(intern_nice_tick(0.19).ptr_eq(&intern_nice_tick(0.25)));
+		assert!(intern_nice_tick(480.0).ptr_eq(js_intern!(500.0)));
+		assert!(!intern_nice_tick(480.0).ptr_eq(&intern_nice_tick(-480.0)));
+	}
+
+	thread_local!(
+		static CLEARS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+	);
+
+	fn count_clear() {
+		CLEARS.with(|c| c.set(c.get() + 1));
+	}
+
+	#[wasm_bindgen_test]
+	fn calls_clear_hook() {
+		on_intern_clear(Some(count_clear));
+		let _ = js_intern!("cleared by hook");
+		unsafe { reset_interner(); }
+		assert_eq!(CLEARS.with(|c| c.get()), 1);
+		let _ = js_intern!("cleared by hook");
+		assert!(unsafe { clear_string_cache_if_over(0) });
+		assert_eq!(CLEARS.with(|c| c.get()), 2);
+		on_intern_clear(None);
+	}
+
+	#[wasm_bindgen_test]
+	fn clear_hook_is_not_called_without_clearing() {
+		on_intern_clear(Some(count_clear));
+		let before = CLEARS.with(|c| c.get());
+		assert!(!unsafe { clear_string_cache_if_over(usize::MAX) });
+		on_intern_clear(None);
+		unsafe { reset_interner(); }
+		assert_eq!(CLEARS.with(|c| c.get()), before);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_cells() {
+		assert_eq!(intern_cell("42").as_f64(), Some(42.0));
+		assert_eq!(intern_cell("4.2e1").as_f64(), Some(42.0));
+		assert_eq!(intern_cell("-0.5").as_f64(), Some(-0.5));
+		for &text in ["abc", "", " 42", "0x2a", "NaN", "inf"].iter() {
+			assert_eq!(intern_cell(text).as_string(), Some(String::from(text)));
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_cells() {
+		assert!(intern_cell("42").ptr_eq(&intern_cell("4.2e1")));
+		assert!(intern_cell("42").ptr_eq(js_intern!(42.0)));
+		assert!(intern_cell("abc").ptr_eq(js_intern!("abc")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_tristate() {
+		assert_eq!(intern_tristate(Some(true)).as_bool(), Some(true));
+		assert_eq!(intern_tristate(Some(false)).as_bool(), Some(false));
+		assert!(intern_tristate(None).is_null());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_tristate() {
+		assert!(intern_tristate(Some(true)).ptr_eq(js_intern!(true)));
+		assert!(intern_tristate(Some(false)).ptr_eq(js_intern!(false)));
+		assert!(intern_tristate(None).ptr_eq(&intern_tristate(None)));
+		intern_debug_assert_interned!(&intern_tristate(None));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_associate_objects() {
+		let pair = intern_kv("associated", "object");
+		let state = Object::new();
+		assert!(get_association(&pair).is_none());
+		assert!(associate(&pair, state.clone().into()));
+		assert!(get_association(&pair).unwrap() == JsValue::from(&state));
+		assert!(associate(&pair, JsValue::UNDEFINED));
+		assert!(get_association(&pair).unwrap().is_undefined());
+		assert!(get_association(&intern_kv("not", "associated")).is_none());
+	}
+
+	#[wasm_bindgen_test]
+	fn cannot_associate_primitives() {
+		assert!(!associate(js_intern!("primitive"), Object::new().into()));
+		assert!(get_association(js_intern!("primitive")).is_none());
+	}
+
+	#[wasm_bindgen_test]
+	fn can_reintern_from_js() {
+		// Functions called from JavaScript get new JsValues for their arguments, like this copy.
+		let roundtrip = |value: &JsValue| Array::of1(value).get(0);
+		assert!(reintern_from_js(&roundtrip(js_intern!("roundtrip"))).unwrap().ptr_eq(js_intern!("roundtrip")));
+		assert!(reintern_from_js(&roundtrip(js_intern!(12.5))).unwrap().ptr_eq(js_intern!(12.5)));
+		assert!(reintern_from_js(&roundtrip(js_intern!(f64::NAN))).unwrap().ptr_eq(js_intern!(f64::NAN)));
+		assert!(reintern_from_js(&roundtrip(js_intern!(true))).unwrap().ptr_eq(js_intern!(true)));
+		assert!(reintern_from_js(&JsValue::UNDEFINED).unwrap().ptr_eq(js_intern!(None::<char>)));
+		assert!(reintern_from_js(&JsValue::NULL).unwrap().ptr_eq(&intern_tristate(None)));
+	}
+
+	#[wasm_bindgen_test]
+	fn reintern_from_js_does_not_intern() {
+		assert!(reintern_from_js(&JsValue::from_str("never interned")).is_none());
+		assert!(get_interned_str("never interned").is_none());
+		assert!(reintern_from_js(&JsValue::from_f64(98765.4321)).is_none());
+		assert!(reintern_from_js(&Object::new()).is_none());
+	}
+
+	#[wasm_bindgen_test]
+	fn can_format_fixed() {
+		let cases = [(12345, 2, "123.45"), (5, 2, "0.05"), (-5, 3, "-0.005"), (100, 0, "100"), (0, 2, "0.00"), (-120, 1, "-12.0"), (i64::MIN, 18, "-9.223372036854775808"), (1, 20, "0.00000000000000000001")];
+		for &(value, scale, fixed) in cases.iter() {
+			assert_eq!(intern_fixed(value, scale).as_string(), Some(String::from(fixed)));
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_fixed() {
+		assert!(intern_fixed(250, 2).ptr_eq(&intern_fixed(250, 2)));
+		assert!(!intern_fixed(250, 2).ptr_eq(&intern_fixed(250, 1)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_str_ci_pair() {
+		let (original, lowercased) = intern_str_ci_pair("Stra\u{df}e HOME");
+		assert_eq!(original.as_string(), Some(String::from("Stra\u{df}e HOME")));
+		assert_eq!(lowercased.as_string(), Some(String::from("stra\u{df}e home")));
+	}
+
+	#[wasm_bindgen_test]
+	fn ci_pairs_share_lowercased() {
+		let (home, lowercased) = intern_str_ci_pair("Home");
+		let (shouted, shouted_lowercased) = intern_str_ci_pair("HOME");
+		assert!(lowercased.ptr_eq(&shouted_lowercased));
+		assert!(lowercased.ptr_eq(js_intern!("home")));
+		assert!(!home.ptr_eq(&shouted));
+		assert!(home.ptr_eq(js_intern!("Home")));
+		assert!(intern_str_ci_pair("home").0.ptr_eq(&lowercased));
+	}
+
+	#[cfg(feature = "prewarm")]
+	register_prewarm!("prewarmed by registry");
+
+	#[cfg(feature = "prewarm")]
+	#[wasm_bindgen_test]
+	fn interns_registered_prewarms() {
+		assert!(prewarm_registered() >= 1);
+		assert!(get_interned_str("prewarmed by registry").unwrap().ptr_eq(js_intern!("prewarmed by registry")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_read_dataview() {
+		let view: &DataView = intern_dataview(&[0xca, 0xfe, 0x00, 0x2a]).as_js_value().dyn_ref().unwrap();
+		assert_eq!(view.byte_length(), 4);
+		assert_eq!(view.get_uint16(0), 0xcafe);
+		assert_eq!(view.get_uint16_endian(2, true), 0x2a00);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_dataview() {
+		static HEADER: &[u8] = &[1, 2, 3];
+		assert!(intern_dataview(HEADER).ptr_eq(&intern_dataview(&[1, 2, 3])));
+		assert!(!intern_dataview(HEADER).ptr_eq(&intern_dataview(&HEADER[1..])));
+		assert!(!intern_dataview(HEADER).ptr_eq(&intern_bytes(HEADER)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_split_classes() {
+		let classes: Vec<String> = intern_classes("  btn\tbtn-primary\n active ").iter().map(|class| class.as_string().unwrap()).collect();
+		assert_eq!(classes, vec!["btn", "btn-primary", "active"]);
+		assert!(intern_classes(" \t ").is_empty());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_classes() {
+		let first = intern_classes("card shadow");
+		let second = intern_classes("shadow card");
+		assert!(first[0].ptr_eq(&second[1]));
+		assert!(first[1].ptr_eq(&second[0]));
+		assert!(first[0].ptr_eq(js_intern!("card")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_bucket_logarithmically() {
+		let cases = [(150.0, 10.0, 100.0), (999.0, 10.0, 100.0), (1000.0, 10.0, 1000.0), (0.05, 10.0, 0.01), (1500.0, 1024.0, 1024.0), (-42.0, 10.0, -10.0), (0.0, 10.0, 0.0), (5.0, 2.0, 4.0)];
+		for &(value, base, bucket) in cases.iter() {
+			assert_eq!(intern_log_bucket(value, base).as_f64(), Some(bucket), "{} in base {}", value, base);
+		}
+		assert_eq!(intern_log_bucket(f64::INFINITY, 10.0).as_f64(), Some(f64::INFINITY));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_log_buckets() {
+		assert!(intern_log_bucket(2048.0, 1024.0).ptr_eq(&intern_log_bucket(1_000_000.0, 1024.0)));
+		assert!(intern_log_bucket(2048.0, 1024.0).ptr_eq(js_intern!(1024.0)));
+		assert!(!intern_log_bucket(2048.0, 1024.0).ptr_eq(&intern_log_bucket(2_000_000.0, 1024.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_uri_component() {
+		assert_eq!(intern_uri_component("a b/c?d=\u{e9}").as_string().unwrap(), "a%20b%2Fc%3Fd%3D%C3%A9");
+		assert_eq!(intern_uri_component("plain-segment_1.~").as_string().unwrap(), "plain-segment_1.~");
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_uri_components() {
+		static SEGMENT: &str = "user name";
+		assert!(intern_uri_component(SEGMENT).ptr_eq(&intern_uri_component(SEGMENT)));
+		assert!(!intern_uri_component(SEGMENT).ptr_eq(&intern_uri_component(&SEGMENT[..4])));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_percent_str() {
+		let cases = [(0.125, 1), (0.5, 0), (1.0, 2), (0.0, 0), (-0.25, 1), (0.33333, 3), (2.5, 0)];
+		for &(ratio, decimals) in cases.iter() {
+			assert_eq!(intern_percent_str(ratio, decimals).as_string().unwrap(), format!("{:.*}%", decimals as usize, ratio * 100.0));
+		}
+		assert_eq!(intern_percent_str(0.125, 1).as_string().unwrap(), "12.5%");
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_percent_strs() {
+		assert!(intern_percent_str(0.42, 1).ptr_eq(&intern_percent_str(0.42, 1)));
+		assert!(!intern_percent_str(0.42, 1).ptr_eq(&intern_percent_str(0.42, 2)));
+		assert!(!intern_percent_str(0.42, 1).ptr_eq(&intern_percent_str(0.43, 1)));
+	}
+
+	#[wasm_bindgen_test]
+	fn evicts_low_scoring_strings() {
+		unsafe { reset_interner(); }
+		let _ = js_intern!("the");
+		let _ = js_intern!("and");
+		let _ = js_intern!("antidisestablishment");
+		let _ = js_intern!(String::from("floccinaucinihilipilification"));
+		let short = js_intern!("the") as *const JsValue;
+		let epoch = intern_epoch();
+		let shortest_first = |s: &&str| -(s.len() as i64);
+		assert_eq!(unsafe { evict_strings_by(shortest_first, 2) }, 2);
+		assert!(intern_epoch() > epoch);
+		assert!(get_interned_str("antidisestablishment").is_none());
+		assert!(get_interned_str("floccinaucinihilipilification").is_none());
+		assert!(get_interned_str("and").unwrap().ptr_eq(js_intern!("and")));
+		assert_eq!(get_interned_str("the").unwrap().as_js_value() as *const JsValue, short);
+	}
+
+	#[wasm_bindgen_test]
+	fn evicting_strings_within_keep_is_a_no_op() {
+		let value = js_intern!("kept regardless") as *const JsValue;
+		let epoch = intern_epoch();
+		assert_eq!(unsafe { evict_strings_by(|_| 0, usize::MAX) }, 0);
+		assert_eq!(intern_epoch(), epoch);
+		assert_eq!(js_intern!("kept regardless") as *const JsValue, value);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_html_entity() {
+		let cases = [('&', "&amp;"), ('<', "&lt;"), ('>', "&gt;"), ('"', "&quot;"), ('\'', "&#39;"), ('a', "a"), ('\u{e9}', "\u{e9}")];
+		for &(c, escaped) in cases.iter() {
+			assert_eq!(intern_html_entity(c).as_string().unwrap(), escaped);
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_html_entities() {
+		assert!(intern_html_entity('&').ptr_eq(&intern_html_entity('&')));
+		assert!(intern_html_entity('&').ptr_eq(js_intern!("&amp;")));
+		assert!(intern_html_entity('x').ptr_eq(js_intern!(Some('x'))));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_str_trim_pair() {
+		let (original, trimmed) = intern_str_trim_pair("\t name \n");
+		assert_eq!(original.as_string().unwrap(), "\t name \n");
+		assert_eq!(trimmed.as_string().unwrap(), "name");
+		let (original, trimmed) = intern_str_trim_pair("   ");
+		assert_eq!(original.as_string().unwrap(), "   ");
+		assert_eq!(trimmed.as_string().unwrap(), "");
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_trimmed_strs() {
+		let (padded, trimmed) = intern_str_trim_pair(" x ");
+		let (bare, bare_trimmed) = intern_str_trim_pair("x");
+		assert!(trimmed.ptr_eq(&bare_trimmed));
+		assert!(bare.ptr_eq(&bare_trimmed));
+		assert!(!padded.ptr_eq(&trimmed));
+		assert!(trimmed.ptr_eq(js_intern!("x")));
+	}
+
+	thread_local!(
+		static NON_LITERAL_LINES: std::cell::RefCell<Vec<u32>> = std::cell::RefCell::default();
+	);
+
+	fn record_non_literal(location: &'static std::panic::Location<'static>) {
+		NON_LITERAL_LINES.with(|l| l.borrow_mut().push(location.line()));
+	}
+
+	#[cfg(all(debug_assertions, feature = "track-call-sites"))]
+	#[wasm_bindgen_test]
+	fn reports_non_literal_uses() {
+		on_non_literal_intern(Some(record_non_literal));
+		let line = line!() + 2;
+		for i in 0..100 {
+			let _ = js_intern!(format!("non-literal {}", i));
+		}
+		on_non_literal_intern(None);
+		assert_eq!(NON_LITERAL_LINES.with(|l| l.borrow_mut().split_off(0)), vec![line]);
+	}
+
+	#[cfg(debug_assertions)]
+	#[wasm_bindgen_test]
+	fn does_not_report_literal_uses() {
+		on_non_literal_intern(Some(record_non_literal));
+		for _ in 0..100 {
+			let _ = js_intern!("literal");
+			let _ = js_intern!(String::from("same every time"));
+		}
+		on_non_literal_intern(None);
+		assert!(NON_LITERAL_LINES.with(|l| l.borrow().is_empty()));
+	}
+
+	#[cfg(not(any(feature = "small", feature = "track-call-sites")))]
+	#[wasm_bindgen_test]
+	fn evaluates_value_once() {
+		let mut evaluations = 0;
+		for _ in 0..3 {
+			let _ = js_intern!({ evaluations += 1; "evaluated once" });
+		}
+		assert_eq!(evaluations, 1);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_common_constants() {
+		assert_eq!(js_intern!(0.0).as_f64().map(f64::to_bits), Some(0.0f64.to_bits()));
+		assert_eq!(js_intern!(1.0).as_f64(), Some(1.0));
+		assert_eq!(js_intern!(-1.0).as_f64(), Some(-1.0));
+		assert_eq!(js_intern!(-0.0).as_f64().map(f64::to_bits), Some((-0.0f64).to_bits()));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_common_constants() {
+		assert_eq!(js_intern!(0.0) as *const _, js_intern!(0u8) as *const _);
+		assert_eq!(js_intern!(1.0) as *const _, js_intern!(1i32) as *const _);
+		assert_eq!(js_intern!(-1.0) as *const _, js_intern!(-1.0f32) as *const _);
+		assert_ne!(js_intern!(0.0) as *const _, js_intern!(-0.0) as *const _);
+		assert!(reintern_from_js(&JsValue::from_f64(1.0)).unwrap().ptr_eq(js_intern!(1.0)));
+		intern_debug_assert_interned!(js_intern!(-1.0));
+		let one = js_intern!(1.0) as *const JsValue;
+		unsafe { reset_interner(); }
+		assert_eq!(js_intern!(1.0) as *const JsValue, one);
+	}
+
+	#[wasm_bindgen_test]
+	fn prewarms_phf_keys() {
+		static KEYWORDS: &[&str] = &["phf if", "phf else", "phf while"];
+		assert!(get_interned_str("phf while").is_none());
+		assert_eq!(prewarm_phf_keys(KEYWORDS), 3);
+		assert!(get_interned_str("phf while").unwrap().ptr_eq(js_intern!("phf while")));
+	}
+
+	#[wasm_bindgen_test]
+	fn prewarms_keys_from_iterators() {
+		static KEYWORDS: [(&str, u32); 2] = [("phf fn", 1), ("phf let", 2)];
+		let keys = KEYWORDS.iter().map(|(key, _)| key);
+		assert_eq!(prewarm_phf_keys(keys), 2);
+		let prewarmed = get_interned_str("phf let").unwrap();
+		assert!(prewarmed.ptr_eq(js_intern!("phf let")));
+		assert_eq!(prewarm_phf_keys(&[]), 0);
+	}
+
+	thread_local!(
+		static ASYNC_MISSES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+	);
+
+	fn count_async_miss(s: &str) {
+		if s.starts_with("converted asynchronously") {
+			ASYNC_MISSES.with(|m| m.set(m.get() + 1));
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_str_async() {
+		use std::future::Future;
+		let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+		let mut first = Box::pin(intern_str_async("converted asynchronously"));
+		let mut second = Box::pin(intern_str_async("converted asynchronously"));
+		on_string_miss(Some(count_async_miss));
+		assert!(first.as_mut().poll(&mut cx).is_pending());
+		assert!(second.as_mut().poll(&mut cx).is_pending());
+		assert!(get_interned_str("converted asynchronously").is_none());
+		let first = match first.as_mut().poll(&mut cx) {
+			std::task::Poll::Ready(value) => value,
+			std::task::Poll::Pending => panic!("the first awaiter should convert on its second poll"),
+		};
+		let second = match second.as_mut().poll(&mut cx) {
+			std::task::Poll::Ready(value) => value,
+			std::task::Poll::Pending => panic!("the second awaiter should share the conversion"),
+		};
+		on_string_miss(None);
+		assert_eq!(ASYNC_MISSES.with(|m| m.get()), 1);
+		assert!(first.ptr_eq(&second));
+		assert!(first.ptr_eq(js_intern!("converted asynchronously")));
+	}
+
+	#[wasm_bindgen_test]
+	fn async_waiters_take_over_dropped_conversions() {
+		use std::future::Future;
+		let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+		let mut first = Box::pin(intern_str_async("abandoned asynchronously"));
+		let mut second = Box::pin(intern_str_async("abandoned asynchronously"));
+		assert!(first.as_mut().poll(&mut cx).is_pending());
+		assert!(second.as_mut().poll(&mut cx).is_pending());
+		drop(first);
+		assert!(second.as_mut().poll(&mut cx).is_pending());
+		match second.as_mut().poll(&mut cx) {
+			std::task::Poll::Ready(value) => assert!(value.ptr_eq(js_intern!("abandoned asynchronously"))),
+			std::task::Poll::Pending => panic!("the waiter should take over the conversion"),
+		}
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_record() {
+		let record = intern_record(&[("width", 640.0), ("height", 480.0), ("width", 800.0)]);
+		assert_eq!(Reflect::get(&record, &JsValue::from_str("width")).unwrap().as_f64(), Some(800.0));
+		assert_eq!(Reflect::get(&record, &JsValue::from_str("height")).unwrap().as_f64(), Some(480.0));
+		assert_eq!(Object::keys(record.unchecked_ref::<Object>()).length(), 2);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_records() {
+		static SIZE: &[(&str, f64)] = &[("w", 1.5), ("h", 2.5)];
+		assert!(intern_record(SIZE).ptr_eq(&intern_record(&[("w", 1.5), ("h", 2.5)])));
+		assert!(!intern_record(SIZE).ptr_eq(&intern_record(&[("h", 2.5), ("w", 1.5)])));
+		assert!(!intern_record(SIZE).ptr_eq(&intern_record(&[("w", 1.5), ("h", 3.5)])));
+		assert!(intern_record(SIZE).ptr_eq(js_intern_object!{ w: 1.5, h: 2.5 }));
+	}
+
+	#[wasm_bindgen_test]
+	fn interns_allowed_strs() {
+		static METHODS: &[&str] = &["GET", "POST", "PUT"];
+		let input = String::from("POST");
+		assert_eq!(intern_str_in_set(&input, METHODS).unwrap().as_string().unwrap(), "POST");
+		assert!(intern_str_in_set(&input, METHODS).unwrap().ptr_eq(js_intern!("POST")));
+	}
+
+	#[wasm_bindgen_test]
+	fn rejects_disallowed_strs() {
+		static METHODS: &[&str] = &["GET", "POST", "PUT"];
+		assert!(intern_str_in_set("get", METHODS).is_none());
+		assert!(intern_str_in_set("not a method", METHODS).is_none());
+		assert!(get_interned_str("not a method").is_none());
+		assert!(intern_str_in_set("GET", &[]).is_none());
+	}
+
+	// Bools are the only values which can be interned without a JavaScript engine, and with the small
+	// feature they are singletons rather than boxed. This runs as a native test, since it spawns a thread.
+	#[cfg(not(feature = "small"))]
+	#[test]
+	fn leaks_values_on_thread_exit() {
+		std::thread::spawn(|| {
+			let value = js_intern!(true);
+			assert!(intern_bool(true).ptr_eq(value));
+			WATCHED.store(value as *const JsValue as usize, AtomicOrdering::Relaxed);
+		}).join().unwrap();
+		assert_eq!(WATCHED_FREED.load(AtomicOrdering::Relaxed), !cfg!(feature = "leak-forever"));
+	}
+
+	// Unlike js_intern!, this looks the value up every time, so that it sees changes to the settings.
+	fn intern_num(value: f64) -> *const JsValue {
+		use js_intern_core::CacheJsIntern__;
+		value.cache_js_intern__()
+	}
+
+	#[wasm_bindgen_test]
+	fn can_canonicalize_nan() {
+		let payload = f64::from_bits(f64::NAN.to_bits() | 0x5);
+		let distinct = intern_num(payload);
+		assert_ne!(distinct, js_intern!(f64::NAN) as *const JsValue);
+		set_canonicalize_nan(true);
+		let other_payload = f64::from_bits(f64::NAN.to_bits() | 0x6);
+		assert_eq!(intern_num(other_payload), js_intern!(f64::NAN) as *const JsValue);
+		assert_eq!(intern_num(payload), js_intern!(f64::NAN) as *const JsValue);
+		set_canonicalize_nan(false);
+		assert_eq!(intern_num(payload), distinct);
+		assert_ne!(intern_num(other_payload), js_intern!(f64::NAN) as *const JsValue);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_collapse_neg_zero() {
+		assert_ne!(intern_num(-0.0), intern_num(0.0));
+		set_collapse_neg_zero(true);
+		assert_eq!(intern_num(-0.0), intern_num(0.0));
+		assert!(reintern_from_js(&JsValue::from_f64(-0.0)).unwrap().ptr_eq(js_intern!(0.0)));
+		set_collapse_neg_zero(false);
+		assert_ne!(intern_num(-0.0), intern_num(0.0));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_pass_text_node_content_without_converting() {
+		// Stands in for document.createTextNode, which isn't available under node.
+		let create_text_node = js_sys::Function::new_with_args("data", "return { nodeValue: data }");
+		let content = intern_domstring("Hello, world");
+		let node = create_text_node.call1(&JsValue::NULL, &content).unwrap();
+		let node_value = Reflect::get(&node, js_intern!("nodeValue")).unwrap();
+		assert_eq!(node_value.as_string().unwrap(), "Hello, world");
+		assert!(Object::is(&node_value, &content));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_deltas() {
+		let deltas: &Float64Array = intern_deltas(&[1.0, 3.0, 2.0, 2.5]).as_js_value().dyn_ref().unwrap();
+		assert_eq!(deltas.to_vec(), vec![2.0, -1.0, 0.5]);
+		let single: &Float64Array = intern_deltas(&[1.0]).as_js_value().dyn_ref().unwrap();
+		assert_eq!(single.length(), 0);
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_deltas() {
+		static DATA: [f64; 3] = [0.0, 10.0, 30.0];
+		assert!(intern_deltas(&DATA).ptr_eq(&intern_deltas(&DATA)));
+	}
+
+	#[wasm_bindgen_test]
+	fn preserves_key_order_of_objects() {
+		let keys = |object: &JsValue| -> Vec<String> {
+			Object::keys(object.unchecked_ref::<Object>()).iter().map(|key| key.as_string().unwrap()).collect()
+		};
+		let record = intern_record(&[("zebra", 1.0), ("apple", 2.0), ("mango", 3.0), ("apple", 4.0)]);
+		assert_eq!(keys(&record), ["zebra", "apple", "mango"]);
+		assert_eq!(keys(js_intern_object!{ second: 2, first: 1 }), ["second", "first"]);
+		assert_eq!(keys(&intern_kv("k", "v")), ["key", "value"]);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_clamp01() {
+		assert_eq!(intern_clamp01(0.25).as_f64(), Some(0.25));
+		assert_eq!(intern_clamp01(1.5).as_f64(), Some(1.0));
+		assert_eq!(intern_clamp01(-0.3).as_f64(), Some(0.0));
+		assert!(intern_clamp01(f64::NAN).as_f64().unwrap().is_nan());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_clamp01() {
+		assert!(intern_clamp01(1.5).ptr_eq(js_intern!(1.0)));
+		assert!(intern_clamp01(-0.3).ptr_eq(js_intern!(0.0)));
+		assert!(intern_clamp01(-0.0).ptr_eq(js_intern!(0.0)));
+		assert!(intern_clamp01(0.5).ptr_eq(&intern_clamp01(0.5)));
+	}
+
+	thread_local!(
+		static INNER_INTERNER: Interner = Interner::builder().factory(|_| JsValue::UNDEFINED).build();
+	);
+
+	// Interning into another interner while converting is fine, since each cache is only borrowed by its own conversion.
+	#[test]
+	fn interner_factory_can_intern_into_another_interner() {
+		let interner = Interner::builder().factory(|key| {
+			if let InternKey::Str(s) = key {
+				INNER_INTERNER.with(|inner| { inner.intern_str(s); });
+			}
+			JsValue::UNDEFINED
+		}).build();
+		let outer = interner.intern_str("nested") as *const JsValue;
+		assert_eq!(outer, interner.intern_str("nested") as *const JsValue);
+		assert_eq!(INNER_INTERNER.with(|inner| inner.iter().count()), 1);
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_symbol_for() {
+		let symbol = intern_symbol_for("app.id");
+		assert_eq!(symbol.js_typeof().as_string().unwrap(), "symbol");
+		assert_eq!(js_sys::Symbol::key_for(symbol.unchecked_ref()).as_string().unwrap(), "app.id");
+		assert!(Object::is(&symbol, &js_sys::Symbol::for_("app.id")));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_symbol_for() {
+		assert!(intern_symbol_for("app.key").ptr_eq(&intern_symbol_for("app.key")));
+		assert!(intern_symbol_for("app.key").ptr_eq(&intern_symbol_for(Box::leak(String::from("app.key").into_boxed_str()))));
+		assert!(!intern_symbol_for("app.key").ptr_eq(&intern_symbol_for("app.other")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_str_normalized() {
+		let pipeline = [Normalizer::Trim, Normalizer::Lowercase];
+		assert_eq!(intern_str_normalized("  Hello World ", &pipeline).as_string().unwrap(), "hello world");
+		assert_eq!(intern_str_normalized(" a \t b\n", &[Normalizer::CollapseWhitespace, Normalizer::Uppercase]).as_string().unwrap(), "A B");
+		assert_eq!(intern_str_normalized("e\u{301}", &[Normalizer::Nfc]).as_string().unwrap(), "\u{e9}");
+		assert_eq!(intern_str_normalized(" As is ", &[]).as_string().unwrap(), " As is ");
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_str_normalized() {
+		let pipeline = [Normalizer::Trim, Normalizer::Lowercase];
+		let value = intern_str_normalized(" Normal ", &pipeline);
+		assert!(value.ptr_eq(&intern_str_normalized("NORMAL", &pipeline)));
+		assert!(value.ptr_eq(&intern_str_normalized(&String::from("normal\n"), &pipeline)));
+		assert!(value.ptr_eq(js_intern!("normal")));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_iso_timestamp() {
+		assert_eq!(intern_iso_timestamp(0.0).unwrap().as_string().unwrap(), "1970-01-01T00:00:00.000Z");
+		assert_eq!(intern_iso_timestamp(1_700_000_000_123.0).unwrap().as_string().unwrap(), "2023-11-14T22:13:20.123Z");
+		assert_eq!(intern_iso_timestamp(-1.0).unwrap().as_string().unwrap(), "1969-12-31T23:59:59.999Z");
+		assert!(intern_iso_timestamp(f64::NAN).is_none());
+		assert!(intern_iso_timestamp(f64::INFINITY).is_none());
+		assert!(intern_iso_timestamp(8.64e15 + 1.0).is_none());
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_iso_timestamps() {
+		let timestamp = intern_iso_timestamp(1_000_000_000_000.0).unwrap();
+		assert!(timestamp.ptr_eq(&intern_iso_timestamp(1_000_000_000_000.0).unwrap()));
+		assert!(!timestamp.ptr_eq(&intern_iso_timestamp(1_000_000_000_001.0).unwrap()));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_iso_timestamps_in_the_same_millisecond() {
+		let timestamp = intern_iso_timestamp(1_000_000_000_000.2).unwrap();
+		assert!(timestamp.ptr_eq(&intern_iso_timestamp(1_000_000_000_000.7).unwrap()));
+		assert!(timestamp.ptr_eq(&intern_iso_timestamp(1_000_000_000_000.0).unwrap()));
+		let epoch = intern_iso_timestamp(0.0).unwrap();
+		assert!(epoch.ptr_eq(&intern_iso_timestamp(-0.0).unwrap()));
+		assert!(epoch.ptr_eq(&intern_iso_timestamp(0.7).unwrap()));
+		assert!(epoch.ptr_eq(&intern_iso_timestamp(-0.5).unwrap()));
+	}
+
+	// The string cache is keyed by the contents of each string, so equal strings at different addresses share one value.
+	#[wasm_bindgen_test]
+	fn deduplicates_equal_strings_at_different_addresses() {
+		let first: &'static str = Box::leak(String::from("same contents").into_boxed_str());
+		let second: &'static str = Box::leak(String::from("same contents").into_boxed_str());
+		assert_ne!(first.as_ptr(), second.as_ptr());
+		let interned = js_intern!(first);
+		assert!(std::ptr::eq(interned, js_intern!(second)));
+		assert!(std::ptr::eq(interned, js_intern!("same contents")));
+		assert!(std::ptr::eq(interned, js_intern!(String::from("same contents"))));
+		assert!(intern_cow_str(Cow::Borrowed(second)).ptr_eq(interned));
+		assert!(intern_domstring(second).ptr_eq(interned));
+		assert!(get_interned_str(&String::from("same contents")).unwrap().ptr_eq(interned));
+	}
+
+	#[wasm_bindgen_test]
+	fn can_intern_f64_range() {
+		let range: &Array = intern_f64_range(0.5, 1.5).as_js_value().dyn_ref().unwrap();
+		assert_eq!(range.length(), 2);
+		assert_eq!(range.get(0).as_f64(), Some(0.5));
+		assert_eq!(range.get(1).as_f64(), Some(1.5));
+		assert!(Object::is(&range.get(1), js_intern!(1.5)));
+	}
+
+	#[wasm_bindgen_test]
+	fn deduplicates_f64_ranges() {
+		assert!(intern_f64_range(2.0, 4.0).ptr_eq(&intern_f64_range(2.0, 4.0)));
+		assert!(!intern_f64_range(2.0, 4.0).ptr_eq(&intern_f64_range(2.0, 4.5)));
+		assert!(!intern_f64_range(0.0, 1.0).ptr_eq(&intern_f64_range(-0.0, 1.0)));
+	}
+
+	#[wasm_bindgen_test]
+	fn reloads_strings() {
+		let _ = js_intern!("old only");
+		let _ = js_intern!("old and new");
+		let epoch = intern_epoch();
+		unsafe { reload_strings(&["old and new", "new only"]); }
+		assert_eq!(intern_epoch(), epoch + 1);
+		assert!(get_interned_str("old only").is_none());
+		assert!(get_interned_str("old and new").unwrap().ptr_eq(js_intern!("old and new")));
+		assert!(get_interned_str("new only").unwrap().ptr_eq(js_intern!("new only")));
+	}
+}
@@ -0,0 +1,6 @@
+// Invalid base64 is a compile error rather than a panic at runtime.
+#[test]
+fn js_intern_base64_rejects_wrong_padding() {
+	let cases = trybuild::TestCases::new();
+	cases.compile_fail("tests/ui/base64-padding.rs");
+}
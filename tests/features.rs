@@ -0,0 +1,9 @@
+// Each of the strings, numbers and bools features compiles in the caches for that type, so without one of them
+// js_intern! of that type is a compile error rather than pulling the cache back in.
+#[cfg(all(feature = "strings", not(feature = "numbers")))]
+#[test]
+fn js_intern_only_accepts_enabled_types() {
+	let cases = trybuild::TestCases::new();
+	cases.pass("tests/ui/only-strings-string.rs");
+	cases.compile_fail("tests/ui/only-strings-number.rs");
+}
@@ -0,0 +1,80 @@
+// These run natively, against an Interner with a factory which doesn't need a JavaScript engine,
+// so they exercise the same caches as the rest of the crate without a browser.
+#![cfg(not(target_arch = "wasm32"))]
+
+use js_intern::{InternKey, Interner};
+use proptest::prelude::*;
+use std::cell::Cell;
+use std::collections::HashSet;
+use wasm_bindgen::JsValue;
+
+thread_local!(
+	static CONVERSIONS: Cell<usize> = const { Cell::new(0) };
+);
+
+fn counting_factory(_key: InternKey) -> JsValue {
+	CONVERSIONS.with(|c| c.set(c.get() + 1));
+	JsValue::UNDEFINED
+}
+
+fn counting_interner() -> Interner {
+	CONVERSIONS.with(|c| c.set(0));
+	Interner::builder().factory(counting_factory).build()
+}
+
+fn conversions() -> usize {
+	CONVERSIONS.with(|c| c.get())
+}
+
+proptest! {
+	#[test]
+	fn equal_strings_share_an_address(strings in prop::collection::vec("[a-c]{0,2}", 0..64)) {
+		let interner = counting_interner();
+		let strings: Vec<&'static str> = strings.into_iter().map(|s| &*Box::leak(s.into_boxed_str())).collect();
+		let addresses: Vec<*const JsValue> = strings.iter().map(|&s| interner.intern_str(s) as *const _).collect();
+		for (i, a) in strings.iter().enumerate() {
+			for (j, b) in strings.iter().enumerate() {
+				prop_assert_eq!(a == b, addresses[i] == addresses[j], "{:?} and {:?}", a, b);
+			}
+		}
+		let distinct: HashSet<_> = strings.iter().collect();
+		prop_assert_eq!(conversions(), distinct.len());
+	}
+
+	#[test]
+	fn floats_are_compared_by_bits(values in prop::collection::vec(prop::num::f64::ANY, 0..64)) {
+		let interner = counting_interner();
+		let addresses: Vec<*const JsValue> = values.iter().map(|&value| interner.intern_f64(value) as *const _).collect();
+		for (i, a) in values.iter().enumerate() {
+			for (j, b) in values.iter().enumerate() {
+				prop_assert_eq!(a.to_bits() == b.to_bits(), addresses[i] == addresses[j], "{:?} and {:?}", a, b);
+			}
+		}
+		let distinct: HashSet<_> = values.iter().map(|value| value.to_bits()).collect();
+		prop_assert_eq!(conversions(), distinct.len());
+	}
+
+	#[test]
+	fn bools_convert_at_most_twice(values in prop::collection::vec(any::<bool>(), 0..64)) {
+		let interner = counting_interner();
+		for &value in values.iter() {
+			prop_assert_eq!(interner.intern_bool(value) as *const _, interner.intern_bool(value) as *const _);
+		}
+		let distinct: HashSet<_> = values.iter().collect();
+		prop_assert_eq!(conversions(), distinct.len());
+	}
+
+	#[test]
+	fn new_interners_start_empty(strings in prop::collection::vec("[a-z]{1,4}", 1..16)) {
+		let strings: Vec<&'static str> = strings.into_iter().map(|s| &*Box::leak(s.into_boxed_str())).collect();
+		{
+			let interner = counting_interner();
+			for &s in strings.iter() {
+				interner.intern_str(s);
+			}
+		}
+		let interner = counting_interner();
+		interner.intern_str(strings[0]);
+		prop_assert_eq!(conversions(), 1);
+	}
+}
@@ -0,0 +1,16 @@
+// The literals cover every type, so this needs each of the default features.
+#[cfg(all(feature = "strings", feature = "numbers", feature = "bools"))]
+#[test]
+fn js_intern_strict_only_accepts_literals() {
+	let cases = trybuild::TestCases::new();
+	cases.pass("tests/ui/strict-literal.rs");
+	cases.compile_fail("tests/ui/strict-expression.rs");
+}
+
+// Opting in with const only interns expressions which can be evaluated at compile time.
+#[cfg(feature = "strings")]
+#[test]
+fn try_js_intern_const_rejects_runtime_calls() {
+	let cases = trybuild::TestCases::new();
+	cases.compile_fail("tests/ui/try-const-call.rs");
+}
@@ -0,0 +1,14 @@
+#[cfg(all(feature = "strict-literals", feature = "strings", feature = "numbers", feature = "bools"))]
+#[test]
+fn js_intern_only_accepts_literals_with_strict_literals() {
+	let cases = trybuild::TestCases::new();
+	cases.pass("tests/ui/strict-literals-literal.rs");
+	cases.compile_fail("tests/ui/strict-literals-expression.rs");
+}
+
+#[cfg(all(not(feature = "strict-literals"), feature = "strings"))]
+#[test]
+fn js_intern_accepts_expressions_by_default() {
+	let cases = trybuild::TestCases::new();
+	cases.pass("tests/ui/permissive-expression.rs");
+}
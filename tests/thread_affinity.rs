@@ -0,0 +1,24 @@
+// Interned values belong to the thread which interned them, which is checked at compile time
+// rather than at runtime, since neither interners nor handles can be sent to another thread.
+// The handle is of an interned string, which needs the strings feature.
+#[cfg(feature = "strings")]
+#[test]
+fn interned_values_stay_on_their_thread() {
+	let cases = trybuild::TestCases::new();
+	cases.compile_fail("tests/ui/send-handle.rs");
+}
+
+// Implemented twice for types which are Send, so that naming the method is ambiguous and doesn't compile.
+trait AmbiguousIfSend<A> {
+	fn check() {}
+}
+
+impl<T: ?Sized> AmbiguousIfSend<()> for T {}
+impl<T: ?Sized + Send> AmbiguousIfSend<u8> for T {}
+
+// Rather than matching the compiler's explanation of why Interner isn't Send, which names private
+// fields and standard library internals, this only compiles as long as it isn't.
+#[test]
+fn interner_stays_on_its_thread() {
+	<js_intern::Interner as AmbiguousIfSend<_>>::check();
+}
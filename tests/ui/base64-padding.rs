@@ -0,0 +1,5 @@
+use js_intern::js_intern_base64;
+
+fn main() {
+	let _ = js_intern_base64!("QQ=");
+}
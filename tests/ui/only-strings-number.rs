@@ -0,0 +1,9 @@
+use js_intern::js_intern;
+use wasm_bindgen::JsValue;
+
+#[allow(dead_code)]
+fn number() -> &'static JsValue {
+	js_intern!(1.0f64)
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use js_intern::js_intern;
+use wasm_bindgen::JsValue;
+
+// Interning requires a JavaScript host, so this only checks that the use compiles.
+#[allow(dead_code)]
+fn string() -> &'static JsValue {
+	js_intern!("str")
+}
+
+fn main() {}
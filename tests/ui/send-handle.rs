@@ -0,0 +1,8 @@
+use js_intern::intern_domstring;
+
+fn main() {
+	let handle = intern_domstring("elsewhere");
+	std::thread::spawn(move || {
+		handle.as_string();
+	});
+}
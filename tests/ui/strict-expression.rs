@@ -0,0 +1,6 @@
+use js_intern::js_intern_strict;
+
+fn main() {
+	let name = "str";
+	js_intern_strict!(name);
+}
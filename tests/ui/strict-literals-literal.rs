@@ -0,0 +1,10 @@
+use js_intern::js_intern;
+use wasm_bindgen::JsValue;
+
+// Interning requires a JavaScript host, so this only checks that the uses compile.
+#[allow(dead_code)]
+fn literals() -> [&'static JsValue; 4] {
+	[js_intern!("str"), js_intern!(1.0), js_intern!(-1), js_intern!(false)]
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use js_intern::try_js_intern;
+
+fn not_const() -> &'static str {
+	"runtime"
+}
+
+fn main() {
+	let _ = try_js_intern!(const not_const());
+}